@@ -89,7 +89,7 @@ impl<'a, 'b> Parser {
             )
             .subcommand(
                 SubCommand::with_name("account")
-                    .about("create a new account or switch to an existing account or rename an account")
+                    .about("create, switch to, rename, archive or delete an account")
                     .subcommand(
                         SubCommand::with_name("create")
                             .about("creates a new account")
@@ -117,10 +117,34 @@ impl<'a, 'b> Parser {
                                 Arg::from_usage("[account] -a, --account=<account> 'the account to use'")
                             )
                     )
+                    .subcommand(
+                        SubCommand::with_name("archive")
+                            .about("hides the account from the listings and refuses to receive into it")
+                            .arg(
+                                Arg::from_usage("<name> 'the account name'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("delete")
+                            .about("deletes the account with no funds and no pending transactions")
+                            .arg(
+                                Arg::from_usage("<name> 'the account name'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("unarchive")
+                            .about("restores the archived or deleted account")
+                            .arg(
+                                Arg::from_usage("<name> 'the account name'")
+                            )
+                    )
             )
             .subcommand(
                 SubCommand::with_name("accounts")
-                    .about("lists available accounts")
+                    .about("lists available accounts with their balances")
+                    .arg(
+                        Arg::from_usage("[archived] --archived 'list archived and deleted accounts too'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("info")
@@ -131,6 +155,9 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for inputs'")
                     )
+                    .arg(
+                        Arg::from_usage("[all-accounts] --all-accounts 'display balances of every account and the wallet total'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("contacts")
@@ -152,6 +179,59 @@ impl<'a, 'b> Parser {
                                 Arg::from_usage("<name> 'the contact name'")
                             )
                     )
+                    .subcommand(
+                        SubCommand::with_name("group")
+                            .about("manages groups of contacts")
+                            .subcommand(
+                                SubCommand::with_name("add")
+                                    .about("adds contacts to a group, creates the group if it doesn't exist")
+                                    .arg(
+                                        Arg::from_usage("<name> 'the group name'")
+                                    )
+                                    .arg(
+                                        Arg::from_usage("<members>... 'the contacts to add, for example @alice @bob'")
+                                    )
+                            )
+                            .subcommand(
+                                SubCommand::with_name("remove")
+                                    .about("removes contacts from a group, or the whole group if no contacts are specified")
+                                    .arg(
+                                        Arg::from_usage("<name> 'the group name'")
+                                    )
+                                    .arg(
+                                        Arg::from_usage("[members]... 'the contacts to remove'")
+                                    )
+                            )
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("stats")
+                    .about("displays statistics of the wallet activity")
+                    .arg(
+                        Arg::from_usage("[no-refresh] -n, --no-refresh 'do not contact full node to refresh outputs'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("statement")
+                    .about("displays the balance statement of the account for a period, computed from the transaction log")
+                    .arg(
+                        Arg::from_usage("--from=<date> 'the first day of the statement, YYYY-MM-DD'")
+                    )
+                    .arg(
+                        Arg::from_usage("[to] --to=<date> 'the last day of the statement, YYYY-MM-DD, today by default'")
+                    )
+                    .arg(
+                        Arg::from_usage("[interval] -i, --interval=<interval> 'day, week, month or year, month by default'")
+                    )
+                    .arg(
+                        Arg::from_usage("[account] -a, --account=<account> 'the account, the active account by default'")
+                    )
+                    .arg(
+                        Arg::from_usage("[file] -f, --file=<file> 'save the statement to the file instead of printing it, JSON if the name ends with .json, CSV otherwise'")
+                    )
+                    .arg(
+                        Arg::from_usage("[format] --format=<format> 'csv or json'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("txs_count")
@@ -178,6 +258,104 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[full] -f, --show-full 'display extended information about transaction'")
                     )
+                    .arg(
+                        Arg::from_usage("[tag] --tag=<tag> 'display transactions with this tag only'")
+                    )
+                    .arg(
+                        Arg::from_usage("[note] --note=<text> 'display transactions with the note containing this text only'")
+                    )
+                    .arg(
+                        Arg::from_usage("[type] --type=<type> 'display transactions of this type only: sent, received, coinbase or cancelled'")
+                    )
+                    .arg(
+                        Arg::from_usage("[status] --status=<status> 'display confirmed or unconfirmed transactions only'")
+                    )
+                    .arg(
+                        Arg::from_usage("[from-date] --from-date=<date> 'display transactions created on or after this date, YYYY-MM-DD'")
+                    )
+                    .arg(
+                        Arg::from_usage("[to-date] --to-date=<date> 'display transactions created on or before this date, YYYY-MM-DD'")
+                    )
+                    .arg(
+                        Arg::from_usage("[min-height] --min-height=<height> 'display transactions confirmed at this height or above'")
+                    )
+                    .arg(
+                        Arg::from_usage("[max-height] --max-height=<height> 'display transactions confirmed at this height or below'")
+                    )
+                    .arg(
+                        Arg::from_usage("[min-amount] --min-amount=<amount> 'display transactions with the amount of at least this value'")
+                    )
+                    .arg(
+                        Arg::from_usage("[max-amount] --max-amount=<amount> 'display transactions with the amount of at most this value'")
+                    )
+                    .arg(
+                        Arg::from_usage("[address] --address=<address> 'display transactions with this counterparty address or @contact'")
+                    )
+                    .arg(
+                        Arg::from_usage("[message] --message=<text> 'display transactions with a message containing this text'")
+                    )
+                    .arg(
+                        Arg::from_usage("[with-proof] --with-proof 'display transactions with a payment proof only'")
+                    )
+                    .arg(
+                        Arg::from_usage("[without-proof] --without-proof 'display transactions without a payment proof only'")
+                            .conflicts_with("with-proof")
+                    )
+                    .arg(
+                        Arg::from_usage("[sort] --sort=<order> 'sort transactions by id, date, amount or height'")
+                    )
+                    .arg(
+                        Arg::from_usage("[reverse] --reverse 'reverse the sort order'")
+                    )
+                    .subcommand(
+                        SubCommand::with_name("note")
+                            .about("sets the private note of the transaction")
+                            .arg(
+                                Arg::from_usage("-i, --id=<id> 'the transaction id'")
+                            )
+                            .arg(
+                                Arg::from_usage("[note] 'the note, empty to remove it'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("tag")
+                            .about("adds private tags to the transaction")
+                            .arg(
+                                Arg::from_usage("-i, --id=<id> 'the transaction id'")
+                            )
+                            .arg(
+                                Arg::from_usage("<tags>... 'the tags to add'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("untag")
+                            .about("removes private tags from the transaction")
+                            .arg(
+                                Arg::from_usage("-i, --id=<id> 'the transaction id'")
+                            )
+                            .arg(
+                                Arg::from_usage("<tags>... 'the tags to remove'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("export")
+                            .about("exports the transaction history to a CSV or JSON file")
+                            .arg(
+                                Arg::from_usage("-f, --file=<file> 'the file to write, JSON if the name ends with .json, CSV otherwise'")
+                            )
+                            .arg(
+                                Arg::from_usage("[from-date] --from=<date> 'export transactions created on or after this date, YYYY-MM-DD'")
+                            )
+                            .arg(
+                                Arg::from_usage("[to-date] --to=<date> 'export transactions created on or before this date, YYYY-MM-DD'")
+                            )
+                            .arg(
+                                Arg::from_usage("[account] -a, --account=<account> 'the account to export, the active account by default'")
+                            )
+                            .arg(
+                                Arg::from_usage("[format] --format=<format> 'csv or json'")
+                            )
+                    )
             )
             .subcommand(
                 SubCommand::with_name("output_count")
@@ -201,6 +379,30 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[length] -l, --length=<length> 'the number of outputs to display'")
                     )
+                    .subcommand(
+                        SubCommand::with_name("freeze")
+                            .about("excludes the output from any send")
+                            .arg(
+                                Arg::from_usage("<commit> 'the output commitment'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("unfreeze")
+                            .about("allows the frozen output to be spent again")
+                            .arg(
+                                Arg::from_usage("<commit> 'the output commitment'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("label")
+                            .about("sets the output label. The label can be used with send --outputs")
+                            .arg(
+                                Arg::from_usage("<commit> 'the output commitment'")
+                            )
+                            .arg(
+                                Arg::from_usage("[label] 'the label, empty to remove it'")
+                            )
+                    )
             )
             .subcommand(
                 SubCommand::with_name("listen")
@@ -237,12 +439,32 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[file] -f, --file=<file> 'the file to store the slate in'")
                     )
+                    .arg(
+                        Arg::from_usage("[armor] --armor 'with --file, store the slate as armored text'")
+                            .requires("file")
+                    )
+                    .arg(
+                        Arg::from_usage("[encrypt-to] --encrypt-to=<address> 'with --file, store the armored slate encrypted to the mwcmqs address of the recipient'")
+                            .requires("file")
+                    )
+                    .arg(
+                        Arg::from_usage("[group] --group=<group> 'the contact group to send MWCs to, every member receives the amount'")
+                    )
+                    .arg(
+                        Arg::from_usage("[estimate] --estimate 'print the fee, inputs, change and total amount locked without sending anything'")
+                    )
                     .group(ArgGroup::with_name("destination")
-                        .args(&["to", "file"])
+                        .args(&["to", "file", "group", "estimate"])
                         .required(true)
                     )
                     .arg(
-                        Arg::from_usage("<amount> 'the amount of MWCs to send'")
+                        Arg::from_usage("[amount] 'the amount of MWCs to send'")
+                    )
+                    .arg(
+                        Arg::from_usage("[amounts] --amounts=<file> 'CSV file with a per member amounts for --group, line format: @name,amount'")
+                    )
+                    .arg(
+                        Arg::from_usage("[continue-on-error] --continue-on-error 'for --group, continue with the next member if the send failed'")
                     )
                     .arg(
                         Arg::from_usage("[apisecret] -a, --apisecret=<apisecret> 'receiver wallet apisecret. Applicable to http/https address only. Default: none'")
@@ -251,7 +473,7 @@ impl<'a, 'b> Parser {
                         Arg::from_usage("[expectedproof] -e, --expectedproof=<expectedproof> 'expected proof address of listener wallet. Default: none'")
                     )
                     .arg(
-                        Arg::from_usage("[strategy] -s, --strategy=<strategy> 'the input selection strategy (all/smallest/custom). Default: smallest'")
+                        Arg::from_usage("[strategy] -s, --strategy=<strategy> 'the input selection strategy (all/smallest/custom/largest-first/oldest-first/exact-match/privacy). Default: smallest'")
                     )
                     .arg(
                         Arg::from_usage("[ttl-blocks] -b, --ttl-blocks=<ttl-blocks> 'the number of blocks to consider this transaction valid for. Full nodes will not allow this transaction to confirm after this many blocks. Default is None which means the transaction will always be valid.'")
@@ -266,7 +488,7 @@ impl<'a, 'b> Parser {
                         Arg::from_usage("[message] -g, --message=<message> 'the message to include in the tx'")
                     )
                     .arg(
-                        Arg::from_usage("[outputs] -p, --outputs=<outputs> 'a comma separated list of custom outputs to include in transaction'")
+                        Arg::from_usage("[outputs] -p, --outputs=<outputs> 'a comma separated list of custom outputs to include in transaction: commitments, output labels or ids of the transactions that created them'")
                     )
                     .arg(
                         Arg::from_usage("[version] -v, --version=<version> 'the slate version. Default: latest version'")
@@ -280,12 +502,141 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[proof] --proof 'the transaction is submitted with payment_proof_address'")
                     )
+                    .arg(
+                        Arg::from_usage("[from-account] --from-account=<account> 'the account to send from. Default: the active account'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("consolidate")
+                    .about("merges small outputs into bigger ones with transactions to yourself")
+                    .arg(
+                        Arg::from_usage("[min-outputs] -m, --min-outputs=<min-outputs> 'consolidate only if the wallet has at least this many spendable outputs. Default: 10'")
+                    )
+                    .arg(
+                        Arg::from_usage("[max-per-tx] -x, --max-per-tx=<max-per-tx> 'the maximum number of outputs merged by one transaction. Default: 100'")
+                    )
+                    .arg(
+                        Arg::from_usage("[below] -b, --below=<amount> 'merge only the outputs smaller than this amount'")
+                    )
+                    .arg(
+                        Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for outputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[dry-run] --dry-run 'show the transactions and the estimated fees without sending'")
+                    )
+                    .arg(
+                        Arg::from_usage("[yes] -y, --yes 'post the transactions without asking for the approval'")
+                    )
+                    .arg(
+                        Arg::from_usage("[fluff] -l, --fluff 'the transactions are submitted as a fluff transactions'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("split")
+                    .about("splits your funds into outputs of the chosen sizes with a transaction to yourself")
+                    .arg(
+                        Arg::from_usage("[amounts] -a, --amounts=<amounts> 'a comma separated list of output amounts, for example 10,10,10,5'")
+                    )
+                    .arg(
+                        Arg::from_usage("[count] -n, --count=<count> 'the number of outputs to create, use with --each'")
+                    )
+                    .arg(
+                        Arg::from_usage("[each] -e, --each=<each> 'the amount of every output, use with --count'")
+                    )
+                    .group(ArgGroup::with_name("sizes")
+                        .args(&["amounts", "count"])
+                        .required(true)
+                    )
+                    .arg(
+                        Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for inputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[ttl-blocks] -b, --ttl-blocks=<ttl-blocks> 'the number of blocks to consider this transaction valid for. Default: always valid'")
+                    )
+                    .arg(
+                        Arg::from_usage("[from-account] --from-account=<account> 'the account to split the funds of, the new outputs stay there. Default: the active account'")
+                    )
+                    .arg(
+                        Arg::from_usage("[fluff] -l, --fluff 'the transaction is submitted as a fluff transaction'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("transfer")
+                    .about("moves MWCs between accounts of this wallet")
+                    .arg(
+                        Arg::from_usage("--from=<account> 'the account to send from'")
+                    )
+                    .arg(
+                        Arg::from_usage("--to=<account> 'the account to receive to'")
+                    )
+                    .arg(
+                        Arg::from_usage("<amount> 'the amount of MWCs to transfer'")
+                    )
+                    .arg(
+                        Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for inputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[message] -g, --message=<message> 'the message to include in the tx'")
+                    )
+                    .arg(
+                        Arg::from_usage("[fluff] -l, --fluff 'the transaction is submitted as a fluff transaction'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("send-batch")
+                    .about("sends MWCs to many addresses from a CSV file. Progress is stored, so a failed batch can be restarted")
+                    .arg(
+                        Arg::from_usage("-f, --file=<file> 'CSV file with payments, line format: address or @contact,amount[,message]'")
+                    )
+                    .arg(
+                        Arg::from_usage("[dry-run] --dry-run 'validate addresses and balance without sending'")
+                    )
+                    .arg(
+                        Arg::from_usage("[continue-on-error] --continue-on-error 'continue with the next line if the send failed'")
+                    )
+                    .arg(
+                        Arg::from_usage("[resume] --resume 'continue the batch that was started from this file'")
+                    )
+                    .arg(
+                        Arg::from_usage("[new] --new 'start a new batch from this file, the journal of the previous one is archived'")
+                        .conflicts_with("resume")
+                    )
+                    .arg(
+                        Arg::from_usage("[ttl-blocks] -b, --ttl-blocks=<ttl-blocks> 'the number of blocks to consider every transaction valid for'")
+                    )
+                    .arg(
+                        Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for inputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[change-outputs] -o, --change-outputs=<change-outputs> 'the number of change outputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[fluff] -l, --fluff 'the transactions are submitted as a fluff transactions'")
+                    )
+                    .arg(
+                        Arg::from_usage("[proof] --proof 'the transactions are submitted with payment_proof_address'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("invoice")
-                    .about("sends invoice to an address")
+                    .about("sends invoice to an address or saves it to a file")
                     .arg(
-                        Arg::from_usage("-t, --to=<address> 'the address to send MWCs to'")
+                        Arg::from_usage("[to] -t, --to=<address> 'the address to send the invoice to'")
+                    )
+                    .arg(
+                        Arg::from_usage("[file] -f, --file=<file> 'save the invoice slate to the file'")
+                    )
+                    .arg(
+                        Arg::from_usage("[armor] --armor 'with --file, save the invoice as armored text'")
+                            .requires("file")
+                    )
+                    .arg(
+                        Arg::from_usage("[encrypt-to] --encrypt-to=<address> 'with --file, save the armored invoice encrypted to the mwcmqs address of the payer'")
+                            .requires("file")
+                    )
+                    .group(ArgGroup::with_name("destination")
+                        .args(&["to", "file"])
+                        .required(true)
                     )
                     .arg(
                         Arg::from_usage("<amount> 'the amount of MWCs to send'")
@@ -296,6 +647,133 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[fluff] -l, --fluff 'the transaction is submitted as a fluff transactions'")
                     )
+                    .arg(
+                        Arg::from_usage("[from-account] --from-account=<account> 'the account to receive the invoiced MWCs to. Default: the active account'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("invoices")
+                    .about("lists, approves or rejects the incoming invoices waiting for approval")
+                    .subcommand(
+                        SubCommand::with_name("list")
+                            .about("lists the invoices waiting for approval")
+                    )
+                    .subcommand(
+                        SubCommand::with_name("approve")
+                            .about("pays the invoice and sends it back to the issuer")
+                            .arg(
+                                Arg::from_usage("<id> 'the invoice id'")
+                            )
+                            .arg(
+                                Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for inputs'")
+                            )
+                            .arg(
+                                Arg::from_usage("[from-account] --from-account=<account> 'the account to pay from. Default: the active account'")
+                            )
+                            .arg(
+                                Arg::from_usage("[strategy] -s, --strategy=<strategy> 'the input selection strategy (all/smallest/custom/largest-first/oldest-first/exact-match/privacy). Default: smallest'")
+                            )
+                            .arg(
+                                Arg::from_usage("[outputs] -p, --outputs=<outputs> 'a comma separated list of custom outputs to include in transaction: commitments, output labels or ids of the transactions that created them'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("reject")
+                            .about("discards the invoice")
+                            .arg(
+                                Arg::from_usage("<id> 'the invoice id'")
+                            )
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("request")
+                    .about("creates and tracks the payment requests")
+                    .subcommand(
+                        SubCommand::with_name("create")
+                            .about("creates a payment request, the payer sends the request tag in the message")
+                            .arg(
+                                Arg::from_usage("-a, --amount=<amount> 'the requested amount'")
+                            )
+                            .arg(
+                                Arg::from_usage("[memo] -m, --memo=<memo> 'the memo of the request, like the order number'")
+                            )
+                            .arg(
+                                Arg::from_usage("[expires] -e, --expires=<expires> 'the request lifetime, like 30m, 2h or 7d. Default: never expires'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("list")
+                            .about("lists the payment requests and their state")
+                            .arg(
+                                Arg::from_usage("[no-refresh] -n, --no-refresh 'do not refresh the wallet from the node'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("show")
+                            .about("shows the payment request and the payments received for it")
+                            .arg(
+                                Arg::from_usage("<id> 'the request id'")
+                            )
+                            .arg(
+                                Arg::from_usage("[no-refresh] -n, --no-refresh 'do not refresh the wallet from the node'")
+                            )
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("pay")
+                    .about("pays an invoice slate file and writes the response for the issuer")
+                    .arg(
+                        Arg::from_usage("-f, --file=<file> 'the invoice slate file'")
+                    )
+                    .arg(
+                        Arg::from_usage("[armor] --armor 'write the response as armored text'")
+                    )
+                    .arg(
+                        Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for inputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[change-outputs] -o, --change-outputs=<change-outputs> 'the number of change outputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[from-account] --from-account=<account> 'the account to pay from. Default: the active account'")
+                    )
+                    .arg(
+                        Arg::from_usage("[strategy] -s, --strategy=<strategy> 'the input selection strategy (all/smallest/custom/largest-first/oldest-first/exact-match/privacy). Default: smallest'")
+                    )
+                    .arg(
+                        Arg::from_usage("[outputs] -p, --outputs=<outputs> 'a comma separated list of custom outputs to include in transaction: commitments, output labels or ids of the transactions that created them'")
+                    )
+                    .arg(
+                        Arg::from_usage("[yes] -y, --yes 'pay without asking for the approval'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("pending")
+                    .about("displays unconfirmed transactions with their age, TTL and suggested action")
+                    .arg(
+                        Arg::from_usage("[no-refresh] -n, --no-refresh 'do not contact full node to refresh transactions'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("wait")
+                    .about("waits until the transaction gets the number of confirmations")
+                    .arg(
+                        Arg::from_usage("[id] -i, --id=<id> 'the transaction id'")
+                    )
+                    .arg(
+                        Arg::from_usage("[txid] -t, --txid=<txid> 'the transaction UUID'")
+                    )
+                    .group(ArgGroup::with_name("transaction")
+                        .args(&["id", "txid"])
+                        .multiple(true)
+                        .required(true)
+                    )
+                    .arg(
+                        Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations to wait for. Default: 1'")
+                    )
+                    .arg(
+                        Arg::from_usage("[timeout] --timeout=<timeout> 'give up after this many seconds. Default: wait forever'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("repost")
@@ -313,6 +791,13 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("-i, --id=<id> 'the transaction id'")
                     )
+                    .arg(
+                        Arg::from_usage("[safe] --safe 'spend one of the transaction inputs to yourself, so the cancelled transaction can never be confirmed'")
+                    )
+                    .arg(
+                        Arg::from_usage("[timeout] --timeout=<timeout> 'with --safe, stop waiting for the confirmation after this many seconds. Default: wait forever'")
+                            .requires("safe")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("restore")
@@ -381,12 +866,18 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("-f, --file=<file> 'the slate file'")
                     )
+                    .arg(
+                        Arg::from_usage("[armor] --armor 'write the response as armored text'")
+                    )
                     .arg(
                         Arg::from_usage("[key_id] -k, --key_id=<key_id> 'optional key id for this transaction. Be careful about using this.'")
                     )
                     .arg(
                         Arg::from_usage("[recv_file] -r, --recv_file=<recv_file> 'optional receive file with line by line output sizes in nanomwc.'")
                     )
+                    .arg(
+                        Arg::from_usage("[from-account] --from-account=<account> 'the account to receive MWCs to. Default: the active account'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("encryptslate")
@@ -397,13 +888,20 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[to] -t, --to=<address> 'the address to send MWCs to'")
                     )
+                    .arg(
+                        Arg::from_usage("[armor] --armor 'print the encrypted slate as armored text'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("decryptslate")
                 .about("decrypts a slate")
                     .arg(
-                        Arg::from_usage("-s, --slate=<slate> 'the slate'")
+                        Arg::from_usage("[slate] -s, --slate=<slate> 'the slate'")
+                    )
+                    .arg(
+                        Arg::from_usage("[file] -f, --file=<file> 'the file with the armored encrypted slate'")
                     )
+                    .group(ArgGroup::with_name("source").args(&["slate", "file"]).required(true))
             )
             .subcommand(
                 SubCommand::with_name("showpubkeys")
@@ -32,6 +32,8 @@ pub struct Wallet713Config {
     pub mwc_node_secret: Option<String>,
     pub grinbox_listener_auto_start: Option<bool>,
     pub max_auto_accept_invoice: Option<u64>,
+    // Invoices above max_auto_accept_invoice wait for approval this number of hours. None - no expiry.
+    pub invoice_expiry_hours: Option<u64>,
     pub owner_api: Option<bool>,
     pub owner_api_address: Option<String>,
     pub owner_api_secret: Option<String>,
@@ -53,6 +55,17 @@ pub struct Wallet713Config {
     // Wallet state update frequency. In none, no updates will be run in the background.
     pub wallet_updater_frequency_sec: Option<u32>,
 
+    // Policies for stuck transactions, applied with the wallet updater frequency.
+    // Repost the stored transaction if its kernel is not on chain after this number of blocks.
+    pub auto_repost_blocks: Option<u64>,
+    // Cancel the sent transaction when its TTL cutoff height is reached.
+    pub auto_cancel_expired: Option<bool>,
+    // Cancel the sent transaction if its kernel is not on chain after this number of blocks.
+    pub auto_cancel_after_blocks: Option<u64>,
+
+    // Confirmations for the spendable balance of `accounts` and `info`. Default: 10
+    pub default_min_confirmations: Option<u64>,
+
     /// Electrum nodes for secondary coins
     /// Key: <coin>_[main|test]_[1|2]
     /// Value: url
@@ -97,6 +110,10 @@ pub const WALLET713_CONFIG_HELP: &str =
 # Note! Change it if you really understand what you are going!
 # max_auto_accept_invoice =
 
+# Invoices above max_auto_accept_invoice are stored until they are approved with `invoices approve` or rejected.
+# Discard the invoices nobody approved after this number of hours. By default they are kept.
+# invoice_expiry_hours = 72
+
 # Enable Owner API for this wallet. See for details: https://github.com/mwcproject/mwc713/blob/master/docs/API_documentation.md
 # owner_api = false
 
@@ -137,6 +154,18 @@ pub const WALLET713_CONFIG_HELP: &str =
 # If will be set, will run 'sync' command with defined time interval
 # wallet_updater_frequency_sec =
 
+# Policies for stuck transactions. They are applied by the wallet updater, so wallet_updater_frequency_sec must be set.
+# Every repost and cancel is logged.
+# Repost the stored transaction if its kernel is not on chain after this number of blocks.
+# auto_repost_blocks = 30
+# Cancel the sent transaction and unlock its outputs when its TTL cutoff height is reached.
+# auto_cancel_expired = false
+# Cancel the sent transaction and unlock its outputs if its kernel is not on chain after this number of blocks.
+# auto_cancel_after_blocks = 1440
+
+# Number of confirmations an output needs to count as spendable in `accounts` and `info`.
+# default_min_confirmations = 10
+
 # Electrum X servers that are used for Atomic Swap operations. Each Secondary Currency need
 # its own dedicated Electrum X instance. We highly advise to use your own instance, instead of
 # using those community servers.
@@ -179,6 +208,7 @@ impl Wallet713Config {
             mwc_node_secret: None,
             grinbox_listener_auto_start: None,
             max_auto_accept_invoice: None,
+            invoice_expiry_hours: None,
             owner_api: None,
             owner_api_address: None,
             owner_api_secret: None,
@@ -191,6 +221,10 @@ impl Wallet713Config {
             tls_certificate_key: None,
             config_home: None,
             wallet_updater_frequency_sec: None,
+            auto_repost_blocks: None,
+            auto_cancel_expired: None,
+            auto_cancel_after_blocks: None,
+            default_min_confirmations: None,
             swap_electrumx_addr: Some(
                 [
                     ("btc_main_1", "btc.main1.swap.mwc.mw:8000"),
@@ -352,6 +386,10 @@ impl Wallet713Config {
         self.grinbox_listener_auto_start.unwrap_or(true)
     }
 
+    pub fn default_min_confirmations(&self) -> u64 {
+        self.default_min_confirmations.unwrap_or(10)
+    }
+
 
     pub fn owner_api_address(&self) -> String {
         let chain_type = self.chain.clone();
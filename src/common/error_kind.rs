@@ -27,9 +27,14 @@ pub enum ErrorKind {
     )]
     NonCustomWithOutputs,
     #[fail(
-        display = "invalid selection strategy, use either 'smallest', 'all', or 'custom'"
+        display = "invalid selection strategy, use either 'smallest', 'all', 'custom', 'largest-first', 'oldest-first', 'exact-match' or 'privacy'"
     )]
     InvalidStrategy,
+    #[fail(
+        display = "selection strategy '{}' could not find suitable inputs for this amount",
+        0
+    )]
+    NoInputsForStrategy(String),
     #[fail(
         display = "invalid number of ttl_blocks given: `{}`",
         0
@@ -116,6 +121,11 @@ pub enum ErrorKind {
         0
     )]
     _ContactNotFound(String),
+    #[fail(
+        display = "could not find contact group named `{}`!",
+        0
+    )]
+    GroupNotFound(String),
 
     #[fail(display = "could not parse number from string!")]
     NumberParsingError,
@@ -147,4 +157,12 @@ pub enum ErrorKind {
     FileUnableToDelete(String),
     #[fail(display = "unable to create the file '{}', {}", _0, _1)]
     FileUnableToCreate(String, String),
+    #[fail(display = "timeout, transaction {} didn't get {} confirmations in {} seconds", _0, _1, _2)]
+    WaitTimeout(u32, u64, u64),
+    #[fail(display = "transaction {} is cancelled", _0)]
+    TransactionCancelled(u32),
+    #[fail(display = "transaction {} is expired, ttl cutoff height {} is reached", _0, _1)]
+    TransactionExpired(u32, u64),
+    #[fail(display = "account `{}` is archived, run `account unarchive {}` first", _0, _0)]
+    AccountArchived(String),
 }
@@ -0,0 +1,37 @@
+use std::ffi::OsString;
+use std::fs::{File, rename};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::common::{Error, ErrorKind};
+
+/// Load the json side file of the wallet. None if nothing was stored yet.
+/// `what` names the data at the error message.
+pub fn load<T: DeserializeOwned>(path: &Path, what: &str) -> Result<Option<T>, Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let mut data = String::new();
+    File::open(path)?.read_to_string(&mut data)?;
+    let value = serde_json::from_str(&data)
+        .map_err(|e| ErrorKind::GenericError(format!("Unable to read {} {}, {}", what, path.display(), e)))?;
+    Ok(Some(value))
+}
+
+/// Write to the temp file first, so the crash doesn't leave a broken file
+pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    let mut tmp_name: OsString = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(serde_json::to_string_pretty(value)?.as_bytes())?;
+        file.sync_all()?;
+    }
+    rename(&tmp_path, path)?;
+    Ok(())
+}
@@ -1,6 +1,7 @@
 #[macro_use]
 pub mod macros;
 pub mod config;
+pub mod json_store;
 mod error_kind;
 
 pub use self::error_kind::ErrorKind;
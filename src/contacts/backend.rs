@@ -1,21 +1,23 @@
 use std::cell::RefCell;
-use std::fs::create_dir_all;
+use std::fs::{create_dir_all, read, rename};
 use std::path::Path;
 
 use grin_core::ser::Error as CoreError;
 use grin_core::ser::{Readable, Reader, Writeable, Writer};
 use grin_store::{self, option_to_not_found, to_key};
 use grin_store::Store;
+use chrono::Utc;
 
-use super::types::{AddressBookBackend, AddressBookBatch, Contact};
+use super::types::{AddressBookBackend, AddressBookBatch, Contact, ContactGroup};
 use grin_wallet_impls:: {
     Address,
 
 };
-use common::Error;
+use common::{Error, ErrorKind};
 
 const DB_DIR: &'static str = "contacts";
 const CONTACT_PREFIX: u8 = 'X' as u8;
+const GROUP_PREFIX: u8 = 'G' as u8;
 
 pub struct Backend {
     db: grin_store::Store,
@@ -26,14 +28,26 @@ impl Backend {
         let db_path = Path::new(data_path).join(DB_DIR);
         create_dir_all(&db_path)?;
 
-        let store = match Store::new(db_path.to_str().unwrap(), None, Some(DB_DIR), None) {
+        let db_path_str = db_path.to_str()
+            .ok_or(ErrorKind::GenericError(format!("Contacts DB path {} is not a valid UTF-8", db_path.display())))?
+            .to_string();
+        let store = match Store::new(&db_path_str, None, Some(DB_DIR), None) {
             Ok(store) => store,
             Err(err) => {
                 println!("Error: Unable to open contacts DB, storage is corrupted, {}", err);
-                // Let's recreate the DB. Ignoring any cleaning up errors. The last step let's report an error
-                let _ = std::fs::remove_dir_all(&db_path);
-                let _ = create_dir_all(&db_path);
-                Store::new(db_path.to_str().unwrap(), None, Some(DB_DIR), None)?
+                // Keep the broken DB as a backup, start from the clean one and move there everything we can read.
+                let backup_path = Path::new(data_path).join(format!(
+                    "{}.corrupted.{}",
+                    DB_DIR,
+                    Utc::now().format("%Y%m%d-%H%M%S")
+                ));
+                rename(&db_path, &backup_path)?;
+                create_dir_all(&db_path)?;
+                let store = Store::new(&db_path_str, None, Some(DB_DIR), None)?;
+
+                let report = salvage_contacts(&backup_path, &store);
+                report.print(&backup_path);
+                store
             },
         };
 
@@ -42,6 +56,191 @@ impl Backend {
     }
 }
 
+/// Result of the contacts recovery from the corrupted DB
+struct SalvageReport {
+    /// Names of the contacts that was moved into the new DB
+    recovered: Vec<String>,
+    /// Names of the groups that was moved into the new DB
+    recovered_groups: Vec<String>,
+    /// Description of the records that we found but was unable to read or save
+    lost: Vec<String>,
+    /// false if the backup DB can't be opened and records was extracted from the raw data file.
+    /// In this case we can't tell how many records are lost.
+    from_db: bool,
+}
+
+impl SalvageReport {
+    fn print(&self, backup_path: &Path) {
+        println!("Corrupted contacts DB was moved to {}", backup_path.display());
+        println!("Recovered {} contact(s)", self.recovered.len());
+        for name in &self.recovered {
+            println!("    @{}", name);
+        }
+        println!("Recovered {} group(s)", self.recovered_groups.len());
+        for name in &self.recovered_groups {
+            println!("    {}", name);
+        }
+        if !self.lost.is_empty() {
+            println!("Lost {} record(s)", self.lost.len());
+            for l in &self.lost {
+                println!("    {}", l);
+            }
+        }
+        if !self.from_db {
+            println!("WARNING: Contacts DB backup can't be opened, contacts and groups was recovered from the raw data. Some of them might be lost, please check your contacts list.");
+        }
+    }
+}
+
+/// Raw contact record. Reading it never fails because of content, so we can iterate over broken records.
+struct RawRecord(Vec<u8>);
+
+impl Readable for RawRecord {
+    fn read(reader: &mut dyn Reader) -> Result<RawRecord, CoreError> {
+        Ok(RawRecord(reader.read_bytes_len_prefix()?))
+    }
+}
+
+/// Copy every readable contact and group from the corrupted DB at 'backup_path' into 'store'.
+/// If the backup can't be opened as a DB, the raw data file is scanned for the records.
+fn salvage_contacts(backup_path: &Path, store: &Store) -> SalvageReport {
+    let mut report = SalvageReport {
+        recovered: Vec::new(),
+        recovered_groups: Vec::new(),
+        lost: Vec::new(),
+        from_db: true,
+    };
+
+    let mut contacts: Vec<Contact> = Vec::new();
+    let mut groups: Vec<ContactGroup> = Vec::new();
+
+    let backup = backup_path.to_str()
+        .ok_or(format!("{} is not a valid UTF-8 path", backup_path.display()))
+        .and_then(|path| Store::new(path, None, Some(DB_DIR), None).map_err(|e| format!("{}", e)));
+    match backup {
+        Ok(backup) => {
+            read_records(&backup, CONTACT_PREFIX, "contact", contact_from_json, &mut contacts, &mut report.lost);
+            read_records(&backup, GROUP_PREFIX, "group", group_from_json, &mut groups, &mut report.lost);
+        },
+        Err(_) => {
+            report.from_db = false;
+            scan_raw_records(backup_path, &mut contacts, &mut groups, &mut report.lost);
+        }
+    }
+
+    for contact in contacts {
+        match save_record(store, CONTACT_PREFIX, contact.get_name(), &contact) {
+            Ok(_) => report.recovered.push(contact.get_name().clone()),
+            Err(e) => report.lost.push(format!("contact {}: unable to save, {}", contact.get_name(), e)),
+        }
+    }
+    for group in groups {
+        match save_record(store, GROUP_PREFIX, group.get_name(), &group) {
+            Ok(_) => report.recovered_groups.push(group.get_name().clone()),
+            Err(e) => report.lost.push(format!("group {}: unable to save, {}", group.get_name(), e)),
+        }
+    }
+
+    report
+}
+
+// Read the records with the prefix from the backup DB, unreadable ones are reported as lost
+fn read_records<T, F>(backup: &Store, prefix: u8, kind: &str, parse: F, records: &mut Vec<T>, lost: &mut Vec<String>)
+    where F: Fn(&[u8]) -> Result<T, CoreError>
+{
+    match backup.iter::<RawRecord>(&[prefix]) {
+        Ok(iter) => {
+            for (key, data) in iter {
+                let key_str = String::from_utf8_lossy(key.get(2..).unwrap_or(&[])).to_string();
+                match parse(&data.0) {
+                    Ok(record) => records.push(record),
+                    Err(e) => lost.push(format!("{} {}: {}", kind, key_str, e)),
+                }
+            }
+        }
+        Err(e) => lost.push(format!("Unable to read {} records from the DB backup, {}", kind, e)),
+    }
+}
+
+fn save_record<T: Writeable>(store: &Store, prefix: u8, name: &str, record: &T) -> Result<(), grin_store::Error> {
+    let mut key = name.to_string().into_bytes();
+    let record_key = to_key(prefix, &mut key);
+    store
+        .batch()
+        .and_then(|batch| batch.put_ser(&record_key, record).and_then(|_| batch.commit()))
+}
+
+/// Search the LMDB data file for the JSON contact and group records. Records are stored as a plain json
+/// without nested objects, so they can be found even if the DB structure is broken.
+fn scan_raw_records(backup_path: &Path, contacts: &mut Vec<Contact>, groups: &mut Vec<ContactGroup>, lost: &mut Vec<String>) {
+    // Store is created with a default env name, the data lives at <path>/lmdb/data.mdb
+    let data = match read(backup_path.join("lmdb").join("data.mdb")) {
+        Ok(data) => data,
+        Err(e) => {
+            lost.push(format!("Unable to read the raw contacts data, {}", e));
+            return;
+        }
+    };
+
+    // Single pass, every '}' closes the last '{', so every byte is parsed at most once
+    let mut start: Option<usize> = None;
+    for (pos, b) in data.iter().enumerate() {
+        match *b {
+            b'{' => start = Some(pos),
+            b'}' => {
+                let start = match start.take() {
+                    Some(start) => start,
+                    None => continue,
+                };
+                let record = &data[start..=pos];
+                // Objects without a name are not the records, LMDB pages can have any bytes
+                if !contains(record, b"\"name\"") {
+                    continue;
+                }
+                if contains(record, b"\"members\"") {
+                    match group_from_json(record) {
+                        Ok(group) => if !groups.iter().any(|g| g.get_name() == group.get_name()) {
+                            groups.push(group);
+                        },
+                        Err(e) => lost.push(format!("group record at offset {}: {}", start, e)),
+                    }
+                } else {
+                    match contact_from_json(record) {
+                        Ok(contact) => if !contacts.iter().any(|c| c.get_name() == contact.get_name()) {
+                            contacts.push(contact);
+                        },
+                        Err(e) => lost.push(format!("contact record at offset {}: {}", start, e)),
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+fn contains(data: &[u8], pattern: &[u8]) -> bool {
+    data.windows(pattern.len()).any(|w| w == pattern)
+}
+
+fn contact_from_json(data: &[u8]) -> Result<Contact, CoreError> {
+    let data = std::str::from_utf8(&data).map_err(|e| CoreError::CorruptedData(format!("Unable to read contacts data, {}", e)))?;
+
+    let json: serde_json::Value =
+        serde_json::from_str(&data).map_err(|e| CoreError::CorruptedData(format!("Unable to read contacts data, {}", e)))?;
+
+    let address = json["address"].as_str()
+        .ok_or(CoreError::CorruptedData("Unable to read contacts data, Not found 'address'".to_string()))?;
+    let address = Address::parse(address)
+        .map_err(|_| CoreError::CorruptedData("Unable to read contacts data, Not found 'address'".to_string()))?;
+
+    let name = json["name"].as_str()
+        .ok_or(CoreError::CorruptedData("Unable to read contacts data, Not found 'name'".to_string()))?;
+    let contact = Contact::new(name, address)
+        .map_err(|_| CoreError::CorruptedData("Unable to read contacts data, Not found 'name'".to_string()))?;
+
+    Ok(contact)
+}
+
 impl AddressBookBackend for Backend {
     fn get_contact(&mut self, name: &[u8]) -> Result<Contact, Error> {
         let contact_key = to_key(CONTACT_PREFIX, &mut name.to_vec());
@@ -56,6 +255,19 @@ impl AddressBookBackend for Backend {
         Box::new(self.db.iter(&[CONTACT_PREFIX]).unwrap().map(|x| x.1))
     }
 
+    fn get_group(&mut self, name: &[u8]) -> Result<ContactGroup, Error> {
+        let group_key = to_key(GROUP_PREFIX, &mut name.to_vec());
+        option_to_not_found(
+            self.db.get_ser(&group_key),
+            || format!("Group id: {:x?}", name.to_vec()),
+        )
+        .map_err(|e| e.into())
+    }
+
+    fn groups(&self) -> Box<dyn Iterator<Item = ContactGroup>> {
+        Box::new(self.db.iter(&[GROUP_PREFIX]).unwrap().map(|x| x.1))
+    }
+
     fn batch<'a>(&'a self) -> Result<Box<dyn AddressBookBatch + 'a>, Error> {
         let batch = self.db.batch()?;
         let batch = Batch {
@@ -93,6 +305,27 @@ impl<'a> AddressBookBatch for Batch<'a> {
             .map_err(|e| e.into())
     }
 
+    fn save_group(&mut self, group: &ContactGroup) -> Result<(), Error> {
+        let mut key = group.get_name().to_string().into_bytes();
+        let group_key = to_key(GROUP_PREFIX, &mut key);
+        self.db
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .put_ser(&group_key, group)?;
+        Ok(())
+    }
+
+    fn delete_group(&mut self, name: &[u8]) -> Result<(), Error> {
+        let group_key = to_key(GROUP_PREFIX, &mut name.to_vec());
+        self.db
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .delete(&group_key)
+            .map_err(|e| e.into())
+    }
+
     fn commit(&mut self) -> Result<(), Error> {
         let db = self.db.replace(None);
         db.unwrap().commit()?;
@@ -113,17 +346,119 @@ impl Writeable for Contact {
 impl Readable for Contact {
     fn read(reader: &mut dyn Reader) -> Result<Contact, CoreError> {
         let data = reader.read_bytes_len_prefix()?;
-        let data = std::str::from_utf8(&data).map_err(|e| CoreError::CorruptedData(format!("Unable to read contacts data, {}", e)))?;
+        contact_from_json(&data)
+    }
+}
+
+impl Writeable for ContactGroup {
+    fn write<W: Writer>(&self, writer: &mut W) -> Result<(), CoreError> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| CoreError::CorruptedData(format!("Unable to write contact group data, {}", e)))?;
+        writer.write_bytes(&json.as_bytes())
+    }
+}
+
+impl Readable for ContactGroup {
+    fn read(reader: &mut dyn Reader) -> Result<ContactGroup, CoreError> {
+        let data = reader.read_bytes_len_prefix()?;
+        group_from_json(&data)
+    }
+}
+
+fn group_from_json(data: &[u8]) -> Result<ContactGroup, CoreError> {
+    let data = std::str::from_utf8(&data).map_err(|e| CoreError::CorruptedData(format!("Unable to read contact group data, {}", e)))?;
+    serde_json::from_str(&data).map_err(|e| CoreError::CorruptedData(format!("Unable to read contact group data, {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{remove_dir_all, write};
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    const ADDRESS: &'static str = "http://192.168.0.10:13415";
+
+    fn test_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mwc713-contacts-{}", Uuid::new_v4()));
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn open_store(path: &Path) -> Store {
+        create_dir_all(path).unwrap();
+        Store::new(path.to_str().unwrap(), None, Some(DB_DIR), None).unwrap()
+    }
+
+    fn group(name: &str, members: &[&str]) -> ContactGroup {
+        serde_json::from_value(json!({ "name": name, "members": members })).unwrap()
+    }
+
+    // Writes the bytes as is, like a record broken on the disk
+    struct BrokenRecord(&'static [u8]);
+
+    impl Writeable for BrokenRecord {
+        fn write<W: Writer>(&self, writer: &mut W) -> Result<(), CoreError> {
+            writer.write_bytes(&self.0)
+        }
+    }
+
+    #[test]
+    fn salvage_from_db() {
+        let dir = test_dir();
+        let backup_path = dir.join("backup");
+        {
+            let backup = open_store(&backup_path);
+            let alice = Contact::new("alice", Address::parse(ADDRESS).unwrap()).unwrap();
+            save_record(&backup, CONTACT_PREFIX, "alice", &alice).unwrap();
+            save_record(&backup, CONTACT_PREFIX, "bob", &BrokenRecord(b"{\"name\":\"bob\"}")).unwrap();
+            save_record(&backup, GROUP_PREFIX, "team", &group("team", &["alice", "bob"])).unwrap();
+            save_record(&backup, GROUP_PREFIX, "broken", &BrokenRecord(b"{\"name\":")).unwrap();
+        }
+
+        let store = open_store(&dir.join("new"));
+        let report = salvage_contacts(&backup_path, &store);
+        assert!(report.from_db);
+        assert_eq!(report.recovered, vec!["alice".to_string()]);
+        assert_eq!(report.recovered_groups, vec!["team".to_string()]);
+        assert_eq!(report.lost.len(), 2, "{:?}", report.lost);
+        assert!(report.lost.iter().any(|l| l.starts_with("contact bob")));
+        assert!(report.lost.iter().any(|l| l.starts_with("group broken")));
+
+        let restored: Vec<Contact> = store.iter(&[CONTACT_PREFIX]).unwrap().map(|x| x.1).collect();
+        assert_eq!(restored.len(), 1);
+        let groups: Vec<ContactGroup> = store.iter(&[GROUP_PREFIX]).unwrap().map(|x| x.1).collect();
+        assert_eq!(groups[0].get_members(), &vec!["alice".to_string(), "bob".to_string()]);
+
+        drop(store);
+        let _ = remove_dir_all(&dir);
+    }
 
-        let json: serde_json::Value =
-            serde_json::from_str(&data).map_err(|e| CoreError::CorruptedData(format!("Unable to read contacts data, {}", e)))?;
+    #[test]
+    fn salvage_from_corrupted_data_file() {
+        let dir = test_dir();
+        let backup_path = dir.join("backup");
+        create_dir_all(backup_path.join("lmdb")).unwrap();
 
-        let address = Address::parse(json["address"].as_str().unwrap())
-            .map_err(|_| CoreError::CorruptedData("Unable to read contacts data, Not found 'address'".to_string()))?;
+        // Not an LMDB file, so the backup can't be opened and the raw data is scanned
+        let mut data: Vec<u8> = vec![0xff; 64];
+        data.extend_from_slice(b"{{{ garbage }");
+        data.extend_from_slice(format!("\x00\x10{{\"name\":\"alice\",\"address\":\"{}\"}}\x00", ADDRESS).as_bytes());
+        data.extend_from_slice(b"{\"name\":\"team\",\"members\":[\"alice\"]}");
+        data.extend_from_slice(b"{\"name\":\"bob\",\"address\":\"not an address\"}");
+        data.extend_from_slice(format!("{{\"name\":\"alice\",\"address\":\"{}\"}}", ADDRESS).as_bytes());
+        data.extend_from_slice(&[0xfe; 64]);
+        write(backup_path.join("lmdb").join("data.mdb"), &data).unwrap();
 
-        let contact = Contact::new(json["name"].as_str().unwrap(), address)
-            .map_err(|_| CoreError::CorruptedData("Unable to read contacts data, Not found 'name'".to_string()))?;
+        let store = open_store(&dir.join("new"));
+        let report = salvage_contacts(&backup_path, &store);
+        assert!(!report.from_db);
+        assert_eq!(report.recovered, vec!["alice".to_string()]);
+        assert_eq!(report.recovered_groups, vec!["team".to_string()]);
+        assert_eq!(report.lost.len(), 1, "{:?}", report.lost);
+        assert!(report.lost[0].starts_with("contact record at offset"));
 
-        Ok(contact)
+        drop(store);
+        let _ = remove_dir_all(&dir);
     }
 }
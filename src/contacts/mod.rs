@@ -2,6 +2,6 @@ mod backend;
 mod types;
 pub use self::backend::Backend;
 pub use self::types::{
-    AddressBook, AddressBookBackend, Contact,
+    AddressBook, AddressBookBackend, Contact, ContactGroup,
     DEFAULT_GRINBOX_PORT, DEFAULT_MWCMQS_PORT, DEFAULT_MWCMQS_DOMAIN,
 };
@@ -15,12 +15,16 @@ use grin_wallet_impls:: Address;
 pub trait AddressBookBackend {
     fn get_contact(&mut self, name: &[u8]) -> Result<Contact, Error>;
     fn contacts(&self) -> Box<dyn Iterator<Item = Contact>>;
+    fn get_group(&mut self, name: &[u8]) -> Result<ContactGroup, Error>;
+    fn groups(&self) -> Box<dyn Iterator<Item = ContactGroup>>;
     fn batch<'a>(&'a self) -> Result<Box<dyn AddressBookBatch + 'a>, Error>;
 }
 
 pub trait AddressBookBatch {
     fn save_contact(&mut self, contact: &Contact) -> Result<(), Error>;
     fn delete_contact(&mut self, public_key: &[u8]) -> Result<(), Error>;
+    fn save_group(&mut self, group: &ContactGroup) -> Result<(), Error>;
+    fn delete_group(&mut self, name: &[u8]) -> Result<(), Error>;
     fn commit(&mut self) -> Result<(), Error>;
 }
 
@@ -69,6 +73,50 @@ impl AddressBook {
     pub fn contacts(&self) -> Box<dyn Iterator<Item = Contact>> {
         self.backend.contacts()
     }
+
+    // Add members to the group. Group is created if it doesn't exist. Members must be existing contacts.
+    pub fn add_group_members(&mut self, name: &str, members: &Vec<String>) -> Result<ContactGroup, Error> {
+        for m in members {
+            self.get_contact(m)
+                .map_err(|_| ErrorKind::_ContactNotFound(m.clone()))?;
+        }
+
+        let mut group = self.get_group(name).unwrap_or(ContactGroup::new(name));
+        for m in members {
+            if !group.members.contains(m) {
+                group.members.push(m.clone());
+            }
+        }
+
+        let mut batch = self.backend.batch()?;
+        batch.save_group(&group)?;
+        batch.commit()?;
+        Ok(group)
+    }
+
+    // Remove members from the group. If no members specified, the whole group is removed.
+    pub fn remove_group_members(&mut self, name: &str, members: &Vec<String>) -> Result<(), Error> {
+        let mut group = self.get_group(name)?;
+        let mut batch = self.backend.batch()?;
+        if members.is_empty() {
+            batch.delete_group(name.as_bytes())?;
+        } else {
+            group.members.retain(|m| !members.contains(m));
+            batch.save_group(&group)?;
+        }
+        batch.commit()?;
+        Ok(())
+    }
+
+    pub fn get_group(&mut self, name: &str) -> Result<ContactGroup, Error> {
+        self.backend
+            .get_group(name.as_bytes())
+            .map_err(|_| ErrorKind::GroupNotFound(name.to_string()).into())
+    }
+
+    pub fn groups(&self) -> Box<dyn Iterator<Item = ContactGroup>> {
+        self.backend.groups()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -94,6 +142,38 @@ impl Contact {
     }
 }
 
+/// Named list of contacts. Members are stored as contact names, addresses are resolved at usage time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactGroup {
+    name: String,
+    members: Vec<String>,
+}
+
+impl ContactGroup {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            members: Vec::new(),
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_members(&self) -> &Vec<String> {
+        &self.members
+    }
+}
+
+impl Display for ContactGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let members: Vec<String> = self.members.iter().map(|m| format!("@{}", m)).collect();
+        write!(f, "{}: {}", self.name, members.join(" "))?;
+        Ok(())
+    }
+}
+
 impl Display for Contact {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}: {}", self.name, self.address.to_string())?;
@@ -31,6 +31,8 @@ extern crate url;
 extern crate uuid;
 extern crate ws;
 extern crate semver;
+#[macro_use]
+extern crate lazy_static;
 extern crate commands;
 extern crate enquote;
 
@@ -60,6 +62,7 @@ use std::io::prelude::*;
 use std::io;
 use std::io::{Read, Write, BufReader};
 use std::path::Path;
+use std::time::{Duration, Instant};
 use grin_core::core::Transaction;
 use grin_core::ser;
 
@@ -70,7 +73,6 @@ use grin_util::ZeroingString;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use colored::*;
 use grin_core::core;
-use grin_core::libtx::tx_fee;
 use grin_core::global::{set_mining_mode, ChainTypes};
 #[cfg(not(target_os = "android"))]
 use rustyline::completion::{Completer, FilenameCompleter, Pair};
@@ -98,13 +100,21 @@ use common::{ErrorKind, Error, COLORED_PROMPT, Arc, Mutex};
 #[cfg(not(target_os = "android"))]
 use common::PROMPT;
 use wallet::Wallet;
+use wallet::batch;
+use wallet::invoice_queue::InvoiceQueueHandler;
+use wallet::payment_requests;
+use wallet::selection;
+use wallet::slate_armor::{self, ArmoredSlate};
+use wallet::statement::{self, Interval};
+use wallet::tx_export::ExportFormat;
+use wallet::tx_filter::{TxFilter, TxSort, TxType};
 use contacts::DEFAULT_MWCMQS_PORT;
 use contacts::DEFAULT_MWCMQS_DOMAIN;
 
 use grin_wallet_libwallet::proof::tx_proof::TxProof;
-use grin_wallet_libwallet::Slate;
+use grin_wallet_libwallet::{Slate, StatusMessage, TxLogEntryType};
 use grin_util::secp::key::PublicKey;
-use grin_wallet_impls::{MWCMQPublisher, MWCMQSubscriber, MWCMQSAddress, Publisher, Subscriber, Address, AddressType};
+use grin_wallet_impls::{MWCMQPublisher, MWCMQSubscriber, MWCMQSAddress, Publisher, Subscriber, SubscriptionHandler, Address, AddressType};
 
 use contacts::{AddressBook, Backend, Contact,};
 
@@ -114,6 +124,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::borrow::Borrow;
 use uuid::Uuid;
+use chrono::{DateTime, NaiveDate, Utc};
 use grin_wallet_controller::command;
 use grin_wallet_libwallet::proof::tx_proof;
 use grin_wallet_libwallet::proof::proofaddress;
@@ -230,6 +241,29 @@ fn do_contacts(args: &ArgMatches, address_book: Arc<Mutex<AddressBook>>) -> Resu
     } else if let Some(add_args) = args.subcommand_matches("remove") {
         let name = add_args.value_of("name").unwrap();
         address_book.remove_contact(name)?;
+    } else if let Some(group_args) = args.subcommand_matches("group") {
+        if let Some(add_args) = group_args.subcommand_matches("add") {
+            let name = add_args.value_of("name").unwrap();
+            let members = contact_names(add_args.values_of("members"));
+            let group = address_book.add_group_members(name, &members)?;
+            cli_message!("{}", group);
+        } else if let Some(remove_args) = group_args.subcommand_matches("remove") {
+            let name = remove_args.value_of("name").unwrap();
+            let members = contact_names(remove_args.values_of("members"));
+            address_book.remove_group_members(name, &members)?;
+        } else {
+            let groups: Vec<()> = address_book
+                .groups()
+                .map(|group| {
+                    cli_message!("{}", group);
+                    ()
+                })
+                .collect();
+
+            if groups.len() == 0 {
+                cli_message!("You don't have any contact groups. Consider using `contacts group add` to create one.");
+            }
+        }
     } else {
         let contacts: Vec<()> = address_book
             .contacts()
@@ -244,10 +278,21 @@ fn do_contacts(args: &ArgMatches, address_book: Arc<Mutex<AddressBook>>) -> Resu
                 "Your contact list is empty. Consider using `contacts add` to add a new contact."
             );
         }
+
+        for group in address_book.groups() {
+            cli_message!("group {}", group);
+        }
     }
     Ok(())
 }
 
+// Contact names from the command line, '@' prefix is optional
+fn contact_names(values: Option<clap::Values>) -> Vec<String> {
+    values
+        .map(|v| v.map(|n| n.trim_start_matches('@').to_string()).collect())
+        .unwrap_or(vec![])
+}
+
 const WELCOME_FOOTER: &str = r#"Use `help` to see available commands
 "#;
 
@@ -277,15 +322,56 @@ fn start_mwcmqs_listener(
 
     println!("Starting mwcmqs listener...");
 
-    let res = grin_wallet_controller::controller::start_mwcmqs_listener(
+    // Invoices above max_auto_accept_invoice are queued for approval instead of being rejected
+    let address_pub_key = wallet.lock().get_payment_proof_address_pubkey()?;
+    let mwcmqs_address = config.get_mwcmqs_address(&address_pub_key)?;
+    let controller = grin_wallet_controller::controller::Controller::new(
+        &mwcmqs_address.get_stripped(),
         wallet.lock().get_wallet_instance()?,
-        config.get_mqs_config(),
-        false,
         Arc::new(Mutex::new(None)),
+        config.max_auto_accept_invoice,
         false,
-    )?;
+    );
+    let handler = InvoiceQueueHandler::new(
+        controller,
+        config.max_auto_accept_invoice,
+        config.invoice_expiry_hours,
+        wallet.lock().get_data_dir()?,
+    );
+
+    start_mwcmqs_broker(config, wallet, mwcmqs_address, Box::new(handler))
+}
+
+// The wiring of grin_wallet_controller::controller::start_mwcmqs_listener. The library builds the handler
+// itself, so only the handler is different here.
+fn start_mwcmqs_broker(
+    config: &Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    mwcmqs_address: MWCMQSAddress,
+    handler: Box<dyn SubscriptionHandler + Send>,
+) -> Result<(MWCMQPublisher, MWCMQSubscriber), Error> {
+    let mwcmqs_secret_key = wallet.lock().get_payment_proof_address_secret()?;
+    let mqs_config = config.get_mqs_config();
+    let publisher = MWCMQPublisher::new(
+        mwcmqs_address,
+        &mwcmqs_secret_key,
+        mqs_config.mwcmqs_domain,
+        mqs_config.mwcmqs_port,
+        false,
+        handler,
+    );
+    let subscriber = MWCMQSubscriber::new(&publisher);
+
+    let mut cloned_subscriber = subscriber.clone();
+    thread::Builder::new()
+        .name("mwcmqs-broker".to_string())
+        .spawn(move || {
+            if let Err(e) = cloned_subscriber.start() {
+                cli_message!("ERROR: mwcmqs listener stopped, {}", e);
+            }
+        })?;
 
-    Ok(res)
+    Ok((publisher, subscriber))
 }
 
 fn start_tor_listener(
@@ -834,209 +920,1086 @@ fn password_prompt(opt: Option<&str>) -> String {
     })
 }
 
-// fn proof_ok(
-//     sender: Option<String>,
-//     receiver: String,
-//     amount: u64,
-//     outputs: Vec<String>,
-//     kernel: String,
-// ) {
-//     let sender_message = sender
-//         .as_ref()
-//         .map(|s| format!(" from [{}]", s.bright_green()))
-//         .unwrap_or(String::new());
-//
-//     let tor_sender_message = sender
-//         .as_ref()
-//         .map(|s| format!(" from [{}{}{}]","http://".bright_green(), s.bright_green(), ".onion".bright_green()))
-//         .unwrap_or(String::new());
-//
-//     if receiver.len() == 56 {
-//         println!(
-//             "this file proves that [{}] MWCs was sent to [{}]{}",
-//             core::amount_to_hr_string(amount, false).bright_green(),
-//             format!("{}{}{}","http://".bright_green(), receiver.bright_green(), ".onion".bright_green()),
-//             tor_sender_message
-//         );
-//
-//     } else {
-//         println!(
-//             "this file proves that [{}] MWCs was sent to [{}]{}",
-//             core::amount_to_hr_string(amount, false).bright_green(),
-//             receiver.bright_green(),
-//             sender_message
-//         );
-//     }
-//
-//     if sender.is_none() {
-//         println!(
-//             "{}: this proof does not prove which address sent the funds, only which received it",
-//             "WARNING".bright_yellow()
-//         );
-//     }
-//
-//     println!("\noutputs:");
-//     if global::is_mainnet() {
-//         for output in outputs {
-//             println!("   {}: https://explorer.mwc.mw/#o{}", output.bright_magenta(), output);
-//         }
-//         println!("kernel:");
-//         println!("   {}: https://explorer.mwc.mw/#k{}", kernel.bright_magenta(), kernel);
-//     } else {
-//         for output in outputs {
-//             println!("   {}: https://explorer.floonet.mwc.mw/#o{}", output.bright_magenta(), output);
-//         }
-//         println!("kernel:");
-//         println!("   {}: https://explorer.floonet.mwc.mw/#k{}", kernel.bright_magenta(), kernel);
-//     }
-//     println!("\n{}: this proof should only be considered valid if the kernel is actually on-chain with sufficient confirmations", "WARNING".bright_yellow());
-//     println!("please use a mwc block explorer to verify this is the case.");
-// }
+// Run f with the status channel, sync progress is printed to the console until f is done
+fn with_status_updater<T, F>(f: F) -> Result<T, Error>
+    where F: FnOnce(&Option<Sender<StatusMessage>>) -> Result<T, Error>
+{
+    let running = Arc::new( AtomicBool::new(true) );
+    let (tx, rx) = mpsc::channel();
+    let updater = grin_wallet_libwallet::api_impl::owner_updater::start_updater_console_thread(rx, running.clone())?;
 
-fn do_command(
-    command: &str,
-    config: &mut Wallet713Config,
+    let res = f(&Some(tx));
+
+    running.store(false, Ordering::Relaxed);
+    let _ = updater.join();
+    res
+}
+
+// Print the result of the send estimate. Estimate slate amount is the total amount locked.
+fn print_send_estimate(amount: u64, estimate: &Slate, change_outputs: u32) {
+    let total_locked = estimate.amount;
+    let change = total_locked.saturating_sub(amount + estimate.fee);
+    let change_outputs = if change > 0 { change_outputs as usize } else { 0 };
+
+    cli_message!("Estimate for sending {} MWC, nothing is locked:", core::amount_to_hr_string(amount, false));
+    cli_message!("    Fee:            {} MWC", core::amount_to_hr_string(estimate.fee, false));
+    cli_message!("    Inputs:         {}", estimate.tx.body.inputs.len());
+    cli_message!("    Change outputs: {}", change_outputs);
+    cli_message!("    Change:         {} MWC", core::amount_to_hr_string(change, false));
+    cli_message!("    Total locked:   {} MWC", core::amount_to_hr_string(total_locked, false));
+}
+
+// Poll the node until the transaction gets the confirmations. timeout 0 means wait forever.
+fn wait_for_confirmations(
+    id: Option<u32>,
+    slate_id: Option<Uuid>,
+    confirmations: u64,
+    timeout: u64,
     wallet: Arc<Mutex<Wallet>>,
-    address_book: Arc<Mutex<AddressBook>>,
-    mwcmqs_broker: &mut Option<(MWCMQPublisher, MWCMQSubscriber)>,
-    out_is_safe: &mut bool,
-    tor_state: &mut Option<std::sync::Arc<std::sync::Mutex<u32>>>,
-    tor_running: &mut bool,
 ) -> Result<(), Error> {
-    *out_is_safe = true;
+    let poll_interval = Duration::from_secs(10);
+    let started = Instant::now();
 
-    #[cfg(not(target_os = "android"))]
-        let home_dir = dirs::home_dir()
-        .map(|p| p.to_str().unwrap().to_string())
-        .unwrap_or("~".to_string());
+    loop {
+        let (tx, height) = wallet.lock().get_tx_with_height(true, id, slate_id)?;
 
-    #[cfg(target_os = "android")]
-        let home_dir = std::env::current_exe() //  dirs::home_dir()
-        .map(|p| { let mut p = p.clone(); p.pop();  p.to_str().unwrap().to_string()})
-        .unwrap_or("~".to_string());
+        if tx.is_cancelled() {
+            return Err(ErrorKind::TransactionCancelled(tx.id).into());
+        }
+        if tx.confirmed {
+            let tx_confirmations = (height + 1).saturating_sub(tx.output_height);
+            if tx_confirmations >= confirmations {
+                cli_message!("Transaction {} is confirmed, {} confirmations", tx.id, tx_confirmations);
+                return Ok(());
+            }
+        } else if let Some(ttl_cutoff_height) = tx.ttl_cutoff_height {
+            if height >= ttl_cutoff_height {
+                return Err(ErrorKind::TransactionExpired(tx.id, ttl_cutoff_height).into());
+            }
+        }
 
-    let matches = Parser::parse(command)?;
-    match matches.subcommand_name() {
-        Some("config") => {
-            let args = matches.subcommand_matches("config").unwrap();
+        let elapsed = started.elapsed();
+        if timeout > 0 && elapsed >= Duration::from_secs(timeout) {
+            return Err(ErrorKind::WaitTimeout(tx.id, confirmations, timeout).into());
+        }
 
-            let new_address_index = match args.is_present("generate-address") {
-                false => None,
-                true => Some({
-                    let index = match args.value_of("generate-address-index") {
-                        Some(index) => u32::from_str_radix(index, 10)
-                            .map_err(|_| ErrorKind::NumberParsingError)?,
-                        None => config.grinbox_address_index.unwrap_or(0) + 1,
-                    };
-                    config.grinbox_address_index = Some(index);
-                    proofaddress::set_address_index(index);
-                    index
-                }),
-            };
+        let sleep_time = if timeout > 0 {
+            std::cmp::min(poll_interval, Duration::from_secs(timeout) - elapsed)
+        } else {
+            poll_interval
+        };
+        thread::sleep(sleep_time);
+    }
+}
 
-            *config = do_config(
-                args,
-                &config.chain,
-                false,
-                new_address_index,
-                config.config_home.as_ref().map(|x| &**x),
-            )?;
+// Cancel the send and spend one of its inputs to yourself, so the original transaction can never be confirmed.
+// The self spend is tracked until the first confirmation.
+fn safe_cancel(id: u32, timeout: u64, wallet: Arc<Mutex<Wallet>>) -> Result<(), Error> {
+    let wait_wallet = wallet.clone();
+    // The input is locked until the cancel, so the self spend can't be built earlier. Everything
+    // that can be checked goes before the cancel, so a failed check leaves the transaction as it is.
+    let input = {
+        let w = wallet.lock();
+        let input = w.safe_cancel_input(id)?;
+        w.check_receive_account(None)?;
+        input
+    };
+    wallet.lock().cancel(id)?;
+
+    let send_params = SendParams {
+        confirmations: 1,
+        strategy: "custom".to_string(),
+        change_outputs: 0,
+        message: None,
+        output_list: Some(vec![input]),
+        version: None,
+        ttl_blocks: 0,
+        fluff: false,
+        do_proof: false,
+        apisecret: None,
+        expected_proof_address: None,
+        from_account: None,
+    };
 
-            if new_address_index.is_some() {
-                show_address(config, wallet, true)?;
-            }
-        }
-        Some("address") => {
-            let args = matches.subcommand_matches("address").unwrap();
+    let res = with_status_updater(|status_send_channel| {
+        let w = wallet.lock();
+        // Without change the self spend pays exactly the fee, see send ALL
+        w.estimate_send_tx(None, 1, 1, "all", 0, 500, send_params.output_list.clone(), 1, status_send_channel)
+            .and_then(|estimate| self_send(&w, estimate.amount.saturating_sub(estimate.fee), &send_params,
+                                           None, 1, None, status_send_channel))
+    });
 
-            let mut show_mqs = args.is_present("mqs-address");
-            let show_addr = args.is_present("provable-address");
-            if !show_mqs && !show_addr {
-                show_mqs = true;
-            }
+    let (slate, ret_id) = res.map_err(|e| ErrorKind::GenericError(format!(
+        "transaction {} is cancelled, but the self spend of its input failed, it is not invalidated on chain. {}", id, e)))?;
+    cli_message!("Transaction {} is cancelled, self spend transaction {} [{}] invalidates it", id, ret_id, slate.id.to_string());
 
-            if show_addr {
-                let address = wallet.lock().get_provable_address(proofaddress::ProofAddressType::Onion)?;
-                println!(
-                    "{}: {}",
-                    "Your file/http wallet address".bright_yellow(),
-                    address.to_string().bright_green()
-                );
-            }
+    cli_message!("Waiting for the self spend transaction {} to be confirmed...", ret_id);
+    wait_for_confirmations(Some(ret_id), None, 1, timeout, wait_wallet)
+}
 
-            if show_mqs {
-                // printing mqs address
-                show_address(config, wallet, true)?;
+/// Send parameters that are common for every recipient of the send
+#[derive(Clone)]
+struct SendParams {
+    confirmations: u64,
+    strategy: String,
+    change_outputs: u32,
+    message: Option<String>,
+    output_list: Option<Vec<String>>,
+    version: Option<u16>,
+    ttl_blocks: u64,
+    fluff: bool,
+    do_proof: bool,
+    apisecret: Option<String>,
+    expected_proof_address: Option<String>,
+    from_account: Option<String>,
+}
+
+// Slate in the oldest version that supports its features, so older wallets can read it
+fn versioned_slate(slate: &Slate) -> VersionedSlate {
+    if slate.payment_proof.is_some() || slate.ttl_cutoff_height.is_some() {
+        warn!("Transaction contains features that require mwc-wallet 3.0.0 or later");
+        warn!("Please ensure the other party is running mwc-wallet v3.0.0 or later before sending");
+        VersionedSlate::into_version(slate.clone(), SlateVersion::V3)
+    } else {
+        let mut s = slate.clone();
+        s.version_info.version = 2;
+        s.version_info.orig_version = 2;
+        VersionedSlate::into_version(s, SlateVersion::V2)
+    }
+}
+
+// Form of the slate file. Responses are written in the same form as the slate they answer.
+#[derive(Debug, Clone, PartialEq)]
+enum SlateFileFormat {
+    Json,
+    Armored,
+    /// Armored and encrypted to the mwcmqs address
+    Encrypted(String),
+}
+
+impl SlateFileFormat {
+    // Output form from --armor and --encrypt-to, `default` if neither is set
+    fn from_args(args: &ArgMatches, default: SlateFileFormat, address_book: Arc<Mutex<AddressBook>>) -> Result<Self, Error> {
+        if let Some(to) = args.value_of("encrypt-to") {
+            let (to, _) = resolve_address(to, address_book)?;
+            if to.address_type() != AddressType::MWCMQS {
+                return Err(ErrorKind::GenericError("slates can be encrypted only to the mwcmqs address".to_string()).into());
             }
+            return Ok(SlateFileFormat::Encrypted(to.to_string()));
         }
-        Some("init") => {
-            *out_is_safe = false;
-            let args = matches.subcommand_matches("init").unwrap();
-            let passphrase = match args.is_present("passphrase") {
-                true => password_prompt(args.value_of("passphrase")),
-                false => "".to_string(),
-            };
-            *out_is_safe = args.value_of("passphrase").is_none();
+        if args.is_present("armor") {
+            // Keep the encryption of the slate we answer
+            return Ok(match default {
+                SlateFileFormat::Json => SlateFileFormat::Armored,
+                f => f,
+            });
+        }
+        Ok(default)
+    }
+}
 
-            if passphrase.is_empty() {
-                println!("{}: wallet with no passphrase.", "WARNING".bright_yellow());
-            }
+// Publisher that only encrypts and decrypts the slates, it is not connected to mwcmqs
+fn offline_publisher(config: &Wallet713Config, w: &Wallet) -> Result<MWCMQPublisher, Error> {
+    let address_pub_key = w.get_payment_proof_address_pubkey()?;
+    let mwcmqs_address = config.get_mwcmqs_address(&address_pub_key)?;
+    let mwcmqs_secret_key = w.get_payment_proof_address_secret()?;
 
-            let passphrase = grin_util::ZeroingString::from(passphrase);
+    let controller = grin_wallet_controller::controller::Controller::new(
+        &mwcmqs_address.get_stripped(),
+        w.get_wallet_instance()?,
+        Arc::new(Mutex::new(None)),
+        config.max_auto_accept_invoice,
+        false,
+    );
 
-            let seed = wallet
-                .lock()
-                .init(config, passphrase.clone(), true)?;
+    Ok(MWCMQPublisher::new(
+        mwcmqs_address,
+        &mwcmqs_secret_key,
+        config.clone().mwcmqs_domain.unwrap_or(DEFAULT_MWCMQS_DOMAIN.to_string()),
+        config.clone().mwcmqs_port.unwrap_or(DEFAULT_MWCMQS_PORT),
+        false,
+        Box::new(controller),
+    ))
+}
 
-            println!("{}", "Press ENTER when you have done so".bright_green().bold());
+// Decrypt the `encryptslate` output. Return the sender address and the slate json.
+fn decrypt_slate_query(publisher: &MWCMQPublisher, query: &str, w: &Wallet) -> Result<(String, String), Error> {
+    let public_key = w.get_payment_proof_address_pubkey()?;
+    let source_address = ProvableAddress::from_pub_key(&public_key);
+    let url = Url::parse(&format!("http://example.com/?{}", query))?;
+
+    let mut from = String::new();
+    let mut signature = String::new();
+    let mut mapmessage = String::new();
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "from" => from = value.to_string(),
+            "signature" => signature = value.to_string(),
+            "mapmessage" => mapmessage = value.to_string(),
+            _ => {}
+        }
+    }
 
-            let mut line = String::new();
-            io::stdout().flush().unwrap();
-            io::stdin().read_line(&mut line).unwrap();
+    let slate = publisher.decrypt_slate(from.clone(), mapmessage, signature, &source_address)?;
+    Ok((from, slate))
+}
 
-            {
-                let mut wallet_inst = wallet.lock();
-                wallet_inst.complete(seed, config, "default", passphrase, true)?;
-                wallet_inst.update_tip_as_last_scanned()?;
-            }
-            show_address(config, wallet, false)?;
+// Read the json or armored slate. Encrypted slates are answered with the slate encrypted to the sender.
+fn read_slate_file(file_name: &str, config: &Wallet713Config, w: &Wallet) -> Result<(Slate, SlateFileFormat), Error> {
+    let mut file = File::open(file_name)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
 
-            return Ok(());
+    if !slate_armor::is_armored(&data) {
+        return Ok((Slate::deserialize_upgrade(&data)?, SlateFileFormat::Json));
+    }
+    match slate_armor::dearmor(&data)? {
+        ArmoredSlate::Plain(json) => Ok((Slate::deserialize_upgrade(&json)?, SlateFileFormat::Armored)),
+        ArmoredSlate::Encrypted(query) => {
+            let publisher = offline_publisher(config, w)?;
+            let (from, json) = decrypt_slate_query(&publisher, &query, w)?;
+            Ok((Slate::deserialize_upgrade(&json)?, SlateFileFormat::Encrypted(from)))
         }
-        Some("unlock") => {
-            let args = matches.subcommand_matches("unlock").unwrap();
-            let account = args.value_of("account").unwrap_or("default");
-            let passphrase = match args.is_present("passphrase") {
-                true => password_prompt(args.value_of("passphrase")),
-                false => "".to_string(),
-            };
-            *out_is_safe = args.value_of("passphrase").is_none();
+    }
+}
 
-            {
-                let mut w = wallet.lock();
-                if !w.is_locked() {
-                    return Err(ErrorKind::WalletAlreadyUnlocked.into());
-                }
-                w.unlock(config, account, ZeroingString::from(passphrase.as_str()))?;
+fn write_slate_file(slate: &Slate, file_name: &str, format: &SlateFileFormat, config: &Wallet713Config, w: &Wallet) -> Result<(), Error> {
+    let data = match format {
+        SlateFileFormat::Json => serde_json::to_string(&versioned_slate(slate))?,
+        SlateFileFormat::Armored => slate_armor::armor(&ArmoredSlate::Plain(serde_json::to_string(&versioned_slate(slate))?)),
+        SlateFileFormat::Encrypted(to) => {
+            let to = MWCMQSAddress::from_str(to)?;
+            let publisher = offline_publisher(config, w)?;
+            slate_armor::armor(&ArmoredSlate::Encrypted(publisher.encrypt_slate(slate, to.borrow())?))
+        }
+    };
+    let mut file = File::create(file_name)?;
+    file.write_all(data.as_bytes())?;
+    Ok(())
+}
+
+// Ask the user to confirm, true if the answer is yes
+fn prompt_yes(question: &str) -> Result<bool, Error> {
+    print!("{} [y/N]: ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+// Input selection strategy and the custom outputs from the send/pay arguments
+fn parse_selection_strategy(args: &ArgMatches) -> Result<(String, Option<Vec<String>>), Error> {
+    let strategy = args.value_of("strategy").unwrap_or("smallest");
+    if strategy != "smallest" && strategy != "all" && strategy != "custom"
+        && !selection::STRATEGIES.contains(&strategy) {
+        return Err(ErrorKind::InvalidStrategy.into());
+    }
+
+    let output_list = match args.value_of("outputs") {
+        None => {
+            if strategy == "custom" {
+                return Err(ErrorKind::CustomWithNoOutputs.into());
             }
+            None
+        }
+        Some(outputs) => {
+            if strategy != "custom" {
+                return Err(ErrorKind::NonCustomWithOutputs.into());
+            }
+            Some(outputs.split(",").map(|s| s.to_string()).collect())
+        }
+    };
+    Ok((strategy.to_string(), output_list))
+}
 
-            show_address(config, wallet.clone(), false)?;
+// Date in YYYY-MM-DD format
+fn parse_naive_date(date: &str) -> Result<NaiveDate, Error> {
+    Ok(NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| ErrorKind::GenericError(format!("invalid date `{}`, expected YYYY-MM-DD", date)))?)
+}
 
-            start_wallet_api(config, wallet)?;
+// Date in YYYY-MM-DD format, the start of the day UTC
+fn parse_date(date: &str) -> Result<DateTime<Utc>, Error> {
+    Ok(DateTime::<Utc>::from_utc(parse_naive_date(date)?.and_hms(0, 0, 0), Utc))
+}
 
-            return Ok(());
+// Transaction filter and sort order from the txs arguments
+fn parse_tx_filter(args: &ArgMatches, address_book: Arc<Mutex<AddressBook>>) -> Result<TxFilter, Error> {
+    let parse_height = |name: &str| -> Result<Option<u64>, Error> {
+        match args.value_of(name) {
+            Some(h) => Ok(Some(h.parse::<u64>().map_err(|_| ErrorKind::GenericError(format!("invalid height `{}`", h)))?)),
+            None => Ok(None),
         }
-        Some("accounts") => {
-            wallet.lock().list_accounts()?;
+    };
+    let parse_amount = |name: &str| -> Result<Option<u64>, Error> {
+        match args.value_of(name) {
+            Some(a) => Ok(Some(core::amount_from_hr_string(a).map_err(|_| ErrorKind::InvalidAmount(a.to_string()))?)),
+            None => Ok(None),
         }
-        Some("account") => {
-            let args = matches.subcommand_matches("account").unwrap();
+    };
 
-            let create_args = args.subcommand_matches("create");
-            let switch_args = args.subcommand_matches("switch");
-            let rename_args = args.subcommand_matches("rename");
-            if let Some(args) = create_args {
-                wallet
+    let tx_type = match args.value_of("type") {
+        Some(t) => Some(TxType::from_str(t)?),
+        None => None,
+    };
+    let confirmed = match args.value_of("status") {
+        Some("confirmed") => Some(true),
+        Some("unconfirmed") => Some(false),
+        Some(s) => return Err(ErrorKind::GenericError(format!("invalid status `{}`, use confirmed or unconfirmed", s)).into()),
+        None => None,
+    };
+    let from_date = match args.value_of("from-date") {
+        Some(d) => Some(parse_date(d)?),
+        None => None,
+    };
+    // to-date is inclusive, the filter takes the start of the next day
+    let to_date = match args.value_of("to-date") {
+        Some(d) => Some(parse_date(d)? + chrono::Duration::days(1)),
+        None => None,
+    };
+    // Contacts are matched by the address, tx log stores it without the contact name
+    let address = match args.value_of("address") {
+        Some(a) if a.starts_with("@") => Some(resolve_address(a, address_book)?.0.get_stripped()),
+        Some(a) => Some(a.to_string()),
+        None => None,
+    };
+    let has_proof = match (args.is_present("with-proof"), args.is_present("without-proof")) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    };
+    let sort = match args.value_of("sort") {
+        Some(s) => Some(TxSort::from_str(s)?),
+        None => None,
+    };
+
+    Ok(TxFilter {
+        tx_type,
+        confirmed,
+        from_date,
+        to_date,
+        min_height: parse_height("min-height")?,
+        max_height: parse_height("max-height")?,
+        min_amount: parse_amount("min-amount")?,
+        max_amount: parse_amount("max-amount")?,
+        address,
+        message: args.value_of("message").map(|s| s.to_string()),
+        has_proof,
+        tag: args.value_of("tag").map(|s| s.to_string()),
+        note: args.value_of("note").map(|s| s.to_string()),
+        sort,
+        reverse: args.is_present("reverse"),
+    })
+}
+
+// Resolve contact name (@name) or address string into the address. Second value is the name to show to the user.
+fn resolve_address(to: &str, address_book: Arc<Mutex<AddressBook>>) -> Result<(Box<dyn Address>, String), Error> {
+    let mut to = to.to_string();
+    let mut display_to = None;
+
+    if to.starts_with("@") {
+        let contact = address_book.lock().get_contact(&to[1..])?;
+        to = contact.get_address().to_string();
+        display_to = Some(contact.get_name().to_string());
+    }
+    // try parse as a general address and fallback to mwcmqs address
+    let address = Address::parse(&to);
+    let address: Result<Box<dyn Address>, Error> = match address {
+        Ok(address) => Ok(address),
+        Err(e) => {
+            Ok(Box::new(MWCMQSAddress::from_str(&to).map_err(|_| e)?) as Box<dyn Address>)
+        }
+    };
+
+    let to = address?;
+    let display_to = display_to.unwrap_or(to.get_stripped());
+    Ok((to, display_to))
+}
+
+// Build the slate and exchange it with the mwcmqs or http address. Outputs are not locked yet.
+fn exchange_slate(
+    w: &Wallet,
+    config: &Wallet713Config,
+    to: &Box<dyn Address>,
+    amount: u64,
+    params: &SendParams,
+    status_send_channel: &Option<Sender<StatusMessage>>,
+) -> Result<Slate, Error> {
+    let slate = w.initiate_send_tx(
+        params.from_account.clone(),
+        Some(to.to_string()),
+        amount,
+        params.confirmations,
+        &params.strategy,
+        params.change_outputs,
+        500,
+        params.message.clone(),
+        params.output_list.clone(),
+        params.version,
+        1,
+        status_send_channel,
+        params.ttl_blocks,
+        params.do_proof,
+    )?;
+
+    let method = match to.address_type() {
+        AddressType::MWCMQS => "mwcmqs",
+        AddressType::Https => "http",
+    };
+
+    let original_slate = slate.clone();
+
+    let sender = grin_wallet_impls::create_sender(method, &to.to_string(), &params.apisecret, Some(config.get_tor_config()))?;
+    let mut slate = sender.send_tx(&slate)?;
+    //check the expected proof address
+    //compare the expected listening wallet proof address  to the value the returned slate. If they don't match, return error
+    if let Some(expected_addr) = &params.expected_proof_address {
+        if let Some(ref p) = slate.payment_proof {
+            let receiver_a = p.clone().receiver_address;
+            if receiver_a.public_key.len() ==56 &&  receiver_a.public_key != *expected_addr {
+                return Err(ErrorKind::ProofAddresMismatch(receiver_a.public_key, expected_addr.clone()).into());
+            }
+        }
+    }
+
+    // Sender can change that, restoring original value
+    slate.ttl_cutoff_height = original_slate.ttl_cutoff_height.clone();
+    // Checking is sender didn't do any harm to slate
+    Slate::compare_slates_send( &original_slate, &slate)?;
+    Ok(slate)
+}
+
+// Build, send, lock and finalize the transaction to the mwcmqs or http address.
+// Return the finalized slate and tx log id.
+fn send_to_address(
+    w: &Wallet,
+    config: &Wallet713Config,
+    to: &Box<dyn Address>,
+    amount: u64,
+    params: &SendParams,
+    status_send_channel: &Option<Sender<StatusMessage>>,
+) -> Result<(Slate, u32), Error> {
+    let mut slate = exchange_slate(w, config, to, amount, params, status_send_channel)?;
+
+    w.tx_lock_outputs(&slate, Some(to.to_string()),0)?;
+    w.finalize_post_slate( &mut slate, params.fluff)?;
+
+    let ret_id = w.get_id(slate.id)?;
+    Ok((slate, ret_id))
+}
+
+// List of (contact name, amount) for the group send. Amount is the same for every member or
+// comes from the CSV file with lines '@name,amount'
+fn group_recipients(
+    group: &str,
+    amount: Option<&str>,
+    amounts_file: Option<String>,
+    address_book: Arc<Mutex<AddressBook>>,
+) -> Result<Vec<(String, u64)>, Error> {
+    let group = address_book.lock().get_group(group)?;
+    let members = group.get_members();
+    if members.is_empty() {
+        return Err(ErrorKind::GenericError(format!("Group {} doesn't have any members", group.get_name())).into());
+    }
+
+    match (amount, amounts_file) {
+        (Some(amount), None) => {
+            let amount = core::amount_from_hr_string(amount).map_err(|_| ErrorKind::InvalidAmount(amount.to_string()))?;
+            Ok(members.iter().map(|m| (m.clone(), amount)).collect())
+        },
+        (None, Some(amounts_file)) => {
+            let file = File::open(&amounts_file)
+                .map_err(|e| ErrorKind::FileNotFound(amounts_file.clone(), format!("{}", e)))?;
+            let mut amounts: Vec<(String, u64)> = Vec::new();
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                let line = line.trim();
+                if line.is_empty() || line.starts_with("#") {
+                    continue;
+                }
+                let fields: Vec<String> = batch::parse_csv_line(line).iter().map(|f| f.trim().to_string()).collect();
+                if fields.len() != 2 {
+                    return Err(ErrorKind::GenericError(format!("Invalid line `{}` at {}, expected: @name,amount", line, amounts_file)).into());
+                }
+                let name = fields[0].trim_start_matches('@').to_string();
+                if !members.contains(&name) {
+                    return Err(ErrorKind::GenericError(format!("@{} from {} is not a member of the group {}", name, amounts_file, group.get_name())).into());
+                }
+                let amount = core::amount_from_hr_string(&fields[1]).map_err(|_| ErrorKind::InvalidAmount(fields[1].clone()))?;
+                amounts.push((name, amount));
+            }
+
+            // Keeping the group order, every member must have the amount
+            let mut res: Vec<(String, u64)> = Vec::new();
+            for m in members {
+                match amounts.iter().find(|a| a.0 == *m) {
+                    Some(a) => res.push(a.clone()),
+                    None => return Err(ErrorKind::GenericError(format!("Amount for @{} not found at {}", m, amounts_file)).into()),
+                }
+            }
+            Ok(res)
+        },
+        (Some(_), Some(_)) => Err(ErrorKind::GenericError("Please specify either amount or --amounts file for the group, not both".to_string()).into()),
+        (None, None) => Err(ErrorKind::GenericError("Please specify amount or --amounts file for the group".to_string()).into()),
+    }
+}
+
+// Send to this wallet: build, lock, receive and finalize in one step. output_amounts are the sizes of the received outputs.
+// Funds are sent from params.from_account and received into dest_account, None is the active account.
+// Return the finalized slate and tx log id of the sender.
+fn self_send(
+    w: &Wallet,
+    amount: u64,
+    params: &SendParams,
+    dest_account: Option<&str>,
+    routputs: usize,
+    output_amounts: Option<Vec<u64>>,
+    status_send_channel: &Option<Sender<StatusMessage>>,
+) -> Result<(Slate, u32), Error> {
+    // Outputs are locked before the receive, a bad destination must fail earlier
+    w.check_receive_account(dest_account)?;
+
+    let address = Some(String::from("self"));
+    let mut slate = w.initiate_send_tx(
+        params.from_account.clone(),
+        address.clone(),
+        amount,
+        params.confirmations,
+        &params.strategy,
+        params.change_outputs,
+        500,
+        params.message.clone(),
+        params.output_list.clone(),
+        params.version,
+        routputs,
+        status_send_channel,
+        params.ttl_blocks,
+        false,
+    )?;
+
+    w.tx_lock_outputs(&slate, address.clone(), 0)?;
+    let res = w.process_sender_initiated_slate(address, &mut slate, None, output_amounts, dest_account)
+        .and_then(|_| w.finalize_post_slate(&mut slate, params.fluff));
+    if let Err(e) = res {
+        // Nothing was posted, release the locked outputs
+        if let Err(cancel_err) = w.cancel_slate(slate.id) {
+            cli_message!("Unable to cancel transaction [{}], its outputs stay locked. {}", slate.id, cancel_err);
+        }
+        return Err(e);
+    }
+
+    let ret_id = w.get_sent_tx_id(params.from_account.clone(), slate.id)?;
+    Ok((slate, ret_id))
+}
+
+// Merge the smallest outputs into one output per transaction. Every batch is a self send
+// that spends up to max_per_tx outputs with no change. Only outputs below the threshold are merged.
+fn consolidate(
+    min_outputs: usize,
+    max_per_tx: usize,
+    below: Option<u64>,
+    dry_run: bool,
+    yes: bool,
+    params: &SendParams,
+    wallet: Arc<Mutex<Wallet>>,
+) -> Result<(), Error> {
+    let outputs: Vec<_> = wallet.lock().spendable_outputs(true, params.confirmations)?
+        .into_iter()
+        .filter(|o| below.map(|b| o.value < b).unwrap_or(true))
+        .collect();
+    if outputs.len() < min_outputs || outputs.len() < 2 {
+        cli_message!("Nothing to consolidate, {} spendable outputs found", outputs.len());
+        return Ok(());
+    }
+
+    // A batch with a single output doesn't merge anything
+    let batches: Vec<Vec<String>> = outputs
+        .chunks(max_per_tx)
+        .filter(|c| c.len() > 1)
+        .map(|c| c.iter().filter_map(|o| o.commit.clone()).collect())
+        .collect();
+
+    // Estimate every batch first, the amount of the transaction is everything minus the fee
+    let mut estimates: Vec<(u64, u64)> = Vec::new();
+    for batch in &batches {
+        let estimate = wallet.lock().estimate_send_tx(
+            params.from_account.clone(),
+            1,
+            params.confirmations,
+            "all",
+            params.change_outputs,
+            500,
+            Some(batch.clone()),
+            1,
+            &None,
+        )?;
+        estimates.push((estimate.amount.saturating_sub(estimate.fee), estimate.fee));
+    }
+
+    cli_message!("Consolidating {} outputs in {} transactions:", outputs.len(), batches.len());
+    for (i, (batch, (amount, fee))) in batches.iter().zip(estimates.iter()).enumerate() {
+        cli_message!(
+            "    {}: {} outputs into {} MWC, fee {} MWC",
+            i + 1,
+            batch.len(),
+            core::amount_to_hr_string(*amount, false),
+            core::amount_to_hr_string(*fee, false)
+        );
+    }
+    let total_fee: u64 = estimates.iter().map(|e| e.1).sum();
+    cli_message!("Estimated total fees: {} MWC", core::amount_to_hr_string(total_fee, false));
+
+    if dry_run {
+        return Ok(());
+    }
+    if !yes && !prompt_yes("Post these transactions?")? {
+        cli_message!("Consolidation is cancelled");
+        return Ok(());
+    }
+
+    with_status_updater(|status_send_channel| {
+        let w = wallet.lock();
+        for (batch, (amount, _fee)) in batches.into_iter().zip(estimates.into_iter()) {
+            let mut params = params.clone();
+            params.strategy = "custom".to_string();
+            params.output_list = Some(batch);
+
+            let (slate, ret_id) = self_send(&w, amount, &params, None, 1, None, status_send_channel)?;
+            cli_message!(
+                "Consolidation transaction [{}] for [{}] MWCs posted, tx id: {}",
+                slate.id.to_string(),
+                core::amount_to_hr_string(slate.amount, false),
+                ret_id
+            );
+        }
+        Ok(())
+    })
+}
+
+// Send payouts from the CSV file. Progress is stored at the journal, rows that are already
+// sent or locked are skipped, so the batch can be restarted after a failure.
+fn send_batch(
+    csv_file: &str,
+    dry_run: bool,
+    continue_on_error: bool,
+    resume: bool,
+    new_batch: bool,
+    params: &SendParams,
+    config: &Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+) -> Result<(), Error> {
+    let payments = batch::read_batch_file(csv_file)?;
+    let data_dir = wallet.lock().get_data_dir()?;
+    let mut journal = batch::BatchJournal::open(&data_dir, csv_file)?;
+    // Same file might be reused for the next payout, the old journal would skip all its rows
+    if journal.processed_rows() > 0 && !resume {
+        if !new_batch {
+            return Err(ErrorKind::GenericError(format!(
+                "{} rows of {} are already processed, see {}. Use --resume to continue this batch or --new to start a new one",
+                journal.processed_rows(), csv_file, journal.get_path().display())).into());
+        }
+        match dry_run {
+            true => journal.rows.clear(),
+            false => journal.archive()?,
+        }
+    }
+    journal.validate(&payments)?;
+
+    let pending: Vec<&(usize, batch::BatchPayment)> = payments.iter().filter(|p| !journal.is_done(p.0)).collect();
+
+    // Addresses are validated first, we don't want to fail in the middle because of a typo
+    let mut addresses: Vec<(Box<dyn Address>, String)> = Vec::new();
+    let mut invalid = 0;
+    for (row, payment) in &pending {
+        match resolve_address(&payment.address, address_book.clone()) {
+            Ok(addr) => addresses.push(addr),
+            Err(e) => {
+                cli_message!("Error: Invalid address `{}` at line {}, {}", payment.address, row, e);
+                invalid += 1;
+            },
+        }
+    }
+    if invalid > 0 {
+        return Err(ErrorKind::GenericError(format!("{} invalid address(es) found at {}", invalid, csv_file)).into());
+    }
+
+    if dry_run {
+        let total: u64 = pending.iter().map(|p| p.1.amount).sum();
+        // Every row is estimated on its own, the rows don't lock the outputs for each other
+        let mut fees: u64 = 0;
+        let mut unaffordable = 0;
+        {
+            let w = wallet.lock();
+            for (row, payment) in &pending {
+                match w.estimate_send_tx(params.from_account.clone(), payment.amount, params.confirmations, &params.strategy,
+                                         params.change_outputs, 500, params.output_list.clone(), 1, &None) {
+                    Ok(estimate) => fees += estimate.fee,
+                    Err(e) => {
+                        cli_message!("Error: Unable to send line {}, {}", row, e);
+                        unaffordable += 1;
+                    },
+                }
+            }
+        }
+        let available = wallet.lock().total_value(true, params.confirmations, &params.output_list)?;
+        cli_message!(
+            "Batch {}: {} rows, {} already processed, {} to send",
+            csv_file,
+            payments.len(),
+            payments.len() - pending.len(),
+            pending.len()
+        );
+        cli_message!(
+            "Total amount: {} MWC, estimated fees: {} MWC, spendable: {} MWC",
+            core::amount_to_hr_string(total, false),
+            core::amount_to_hr_string(fees, false),
+            core::amount_to_hr_string(available, false)
+        );
+        if unaffordable > 0 {
+            return Err(ErrorKind::GenericError(format!("{} row(s) of {} can't be sent", unaffordable, csv_file)).into());
+        }
+        if total + fees > available {
+            return Err(ErrorKind::GenericError("Not enough funds to send the batch".to_string()).into());
+        }
+        cli_message!("All addresses are valid, the batch is ready to send");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    with_status_updater(|status_send_channel| {
+        let w = wallet.lock();
+        for ((row, payment), (to, display_to)) in pending.iter().zip(addresses.iter()) {
+            let mut params = params.clone();
+            params.message = payment.message.clone().or(params.message);
+
+            let slate = match exchange_slate(&w, config, to, payment.amount, &params, status_send_channel) {
+                Ok(slate) => slate,
+                Err(e) => {
+                    cli_message!("Error: Unable to send line {} to [{}], {}", row, display_to, e);
+                    journal.update(*row, payment, batch::BatchRowStatus::Failed, None, None, Some(format!("{}", e)))?;
+                    failed += 1;
+                    if continue_on_error {
+                        continue;
+                    }
+                    break;
+                },
+            };
+
+            // From now the row must never be sent again, outputs are locked.
+            w.tx_lock_outputs(&slate, Some(to.to_string()), 0)?;
+            journal.update(*row, payment, batch::BatchRowStatus::Locked, Some(slate.id), None, None)?;
+
+            let mut slate = slate;
+            match w.finalize_post_slate(&mut slate, params.fluff).and_then(|_| w.get_id(slate.id)) {
+                Ok(id) => {
+                    journal.update(*row, payment, batch::BatchRowStatus::Sent, Some(slate.id), Some(id), None)?;
+                    cli_message!(
+                        "Transaction [{}] for [{}] MWCs sent successfully to [{}]",
+                        slate.id.to_string(),
+                        core::amount_to_hr_string(payment.amount, false),
+                        display_to
+                    );
+                },
+                Err(e) => {
+                    cli_message!("Error: Unable to finalize line {} for [{}], outputs stay locked. {}", row, display_to, e);
+                    journal.update(*row, payment, batch::BatchRowStatus::Locked, Some(slate.id), None, Some(format!("{}", e)))?;
+                    failed += 1;
+                    if !continue_on_error {
+                        break;
+                    }
+                },
+            }
+        }
+        Ok(())
+    })?;
+
+    println!("Batch {} summary:", csv_file);
+    for (row, payment) in &payments {
+        let status = match journal.rows.get(row) {
+            Some(r) => match r.status {
+                batch::BatchRowStatus::Sent => format!("SENT txid={} uuid={}",
+                                                   r.tx_id.map(|id| id.to_string()).unwrap_or("?".to_string()),
+                                                   r.tx_slate_id.map(|id| id.to_string()).unwrap_or("?".to_string())),
+                batch::BatchRowStatus::Locked => format!("LOCKED, NOT FINALIZED uuid={}",
+                                                     r.tx_slate_id.map(|id| id.to_string()).unwrap_or("?".to_string())),
+                batch::BatchRowStatus::Failed => format!("FAILED, {}", r.error.clone().unwrap_or(String::new())),
+            },
+            None => "NOT SENT".to_string(),
+        };
+        println!("    line {} {} [{}] {}", row, payment.address, core::amount_to_hr_string(payment.amount, false), status);
+    }
+    println!("Journal: {}", journal.get_path().display());
+
+    if failed > 0 {
+        return Err(ErrorKind::GenericError(format!("{} batch payments failed, rerun send-batch to retry them", failed)).into());
+    }
+    Ok(())
+}
+
+// Send to every group member one by one. On failure stop or continue with the next member.
+fn send_to_group(
+    group: &str,
+    recipients: Vec<(String, u64)>,
+    continue_on_error: bool,
+    params: &SendParams,
+    config: &Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+) -> Result<(), Error> {
+    // (name, amount, result). Result is None if send wasn't started
+    let mut results: Vec<(String, u64, Option<Result<(Uuid, u32), String>>)> =
+        recipients.iter().map(|r| (r.0.clone(), r.1, None)).collect();
+
+    with_status_updater(|status_send_channel| {
+        let w = wallet.lock();
+        for r in results.iter_mut() {
+            let res = resolve_address(&format!("@{}", r.0), address_book.clone())
+                .and_then(|(to, _)| send_to_address(&w, config, &to, r.1, params, status_send_channel));
+            match res {
+                Ok((slate, id)) => {
+                    cli_message!(
+                        "Transaction [{}] for [{}] MWCs sent successfully to [@{}]",
+                        slate.id.to_string(),
+                        core::amount_to_hr_string(slate.amount, false),
+                        r.0
+                    );
+                    r.2 = Some(Ok((slate.id, id)));
+                },
+                Err(e) => {
+                    cli_message!("Error: Unable to send [{}] MWCs to [@{}], {}", core::amount_to_hr_string(r.1, false), r.0, e);
+                    r.2 = Some(Err(format!("{}", e)));
+                    if !continue_on_error {
+                        break;
+                    }
+                },
+            }
+        }
+        Ok(())
+    })?;
+
+    println!("Group [{}] send summary:", group);
+    let mut failed = 0;
+    for (name, amount, res) in &results {
+        let status = match res {
+            Some(Ok((uuid, id))) => format!("txid={} uuid={}", id, uuid),
+            Some(Err(e)) => { failed += 1; format!("FAILED, {}", e) },
+            None => { failed += 1; "NOT SENT".to_string() },
+        };
+        println!("    @{} [{}] {}", name, core::amount_to_hr_string(*amount, false), status);
+    }
+
+    if failed > 0 {
+        return Err(ErrorKind::GenericError(format!("{} of {} group payments were not sent", failed, results.len())).into());
+    }
+    Ok(())
+}
+
+// fn proof_ok(
+//     sender: Option<String>,
+//     receiver: String,
+//     amount: u64,
+//     outputs: Vec<String>,
+//     kernel: String,
+// ) {
+//     let sender_message = sender
+//         .as_ref()
+//         .map(|s| format!(" from [{}]", s.bright_green()))
+//         .unwrap_or(String::new());
+//
+//     let tor_sender_message = sender
+//         .as_ref()
+//         .map(|s| format!(" from [{}{}{}]","http://".bright_green(), s.bright_green(), ".onion".bright_green()))
+//         .unwrap_or(String::new());
+//
+//     if receiver.len() == 56 {
+//         println!(
+//             "this file proves that [{}] MWCs was sent to [{}]{}",
+//             core::amount_to_hr_string(amount, false).bright_green(),
+//             format!("{}{}{}","http://".bright_green(), receiver.bright_green(), ".onion".bright_green()),
+//             tor_sender_message
+//         );
+//
+//     } else {
+//         println!(
+//             "this file proves that [{}] MWCs was sent to [{}]{}",
+//             core::amount_to_hr_string(amount, false).bright_green(),
+//             receiver.bright_green(),
+//             sender_message
+//         );
+//     }
+//
+//     if sender.is_none() {
+//         println!(
+//             "{}: this proof does not prove which address sent the funds, only which received it",
+//             "WARNING".bright_yellow()
+//         );
+//     }
+//
+//     println!("\noutputs:");
+//     if global::is_mainnet() {
+//         for output in outputs {
+//             println!("   {}: https://explorer.mwc.mw/#o{}", output.bright_magenta(), output);
+//         }
+//         println!("kernel:");
+//         println!("   {}: https://explorer.mwc.mw/#k{}", kernel.bright_magenta(), kernel);
+//     } else {
+//         for output in outputs {
+//             println!("   {}: https://explorer.floonet.mwc.mw/#o{}", output.bright_magenta(), output);
+//         }
+//         println!("kernel:");
+//         println!("   {}: https://explorer.floonet.mwc.mw/#k{}", kernel.bright_magenta(), kernel);
+//     }
+//     println!("\n{}: this proof should only be considered valid if the kernel is actually on-chain with sufficient confirmations", "WARNING".bright_yellow());
+//     println!("please use a mwc block explorer to verify this is the case.");
+// }
+
+fn do_command(
+    command: &str,
+    config: &mut Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+    mwcmqs_broker: &mut Option<(MWCMQPublisher, MWCMQSubscriber)>,
+    out_is_safe: &mut bool,
+    tor_state: &mut Option<std::sync::Arc<std::sync::Mutex<u32>>>,
+    tor_running: &mut bool,
+) -> Result<(), Error> {
+    *out_is_safe = true;
+
+    #[cfg(not(target_os = "android"))]
+        let home_dir = dirs::home_dir()
+        .map(|p| p.to_str().unwrap().to_string())
+        .unwrap_or("~".to_string());
+
+    #[cfg(target_os = "android")]
+        let home_dir = std::env::current_exe() //  dirs::home_dir()
+        .map(|p| { let mut p = p.clone(); p.pop();  p.to_str().unwrap().to_string()})
+        .unwrap_or("~".to_string());
+
+    let matches = Parser::parse(command)?;
+    match matches.subcommand_name() {
+        Some("config") => {
+            let args = matches.subcommand_matches("config").unwrap();
+
+            let new_address_index = match args.is_present("generate-address") {
+                false => None,
+                true => Some({
+                    let index = match args.value_of("generate-address-index") {
+                        Some(index) => u32::from_str_radix(index, 10)
+                            .map_err(|_| ErrorKind::NumberParsingError)?,
+                        None => config.grinbox_address_index.unwrap_or(0) + 1,
+                    };
+                    config.grinbox_address_index = Some(index);
+                    proofaddress::set_address_index(index);
+                    index
+                }),
+            };
+
+            *config = do_config(
+                args,
+                &config.chain,
+                false,
+                new_address_index,
+                config.config_home.as_ref().map(|x| &**x),
+            )?;
+
+            if new_address_index.is_some() {
+                show_address(config, wallet, true)?;
+            }
+        }
+        Some("address") => {
+            let args = matches.subcommand_matches("address").unwrap();
+
+            let mut show_mqs = args.is_present("mqs-address");
+            let show_addr = args.is_present("provable-address");
+            if !show_mqs && !show_addr {
+                show_mqs = true;
+            }
+
+            if show_addr {
+                let address = wallet.lock().get_provable_address(proofaddress::ProofAddressType::Onion)?;
+                println!(
+                    "{}: {}",
+                    "Your file/http wallet address".bright_yellow(),
+                    address.to_string().bright_green()
+                );
+            }
+
+            if show_mqs {
+                // printing mqs address
+                show_address(config, wallet, true)?;
+            }
+        }
+        Some("init") => {
+            *out_is_safe = false;
+            let args = matches.subcommand_matches("init").unwrap();
+            let passphrase = match args.is_present("passphrase") {
+                true => password_prompt(args.value_of("passphrase")),
+                false => "".to_string(),
+            };
+            *out_is_safe = args.value_of("passphrase").is_none();
+
+            if passphrase.is_empty() {
+                println!("{}: wallet with no passphrase.", "WARNING".bright_yellow());
+            }
+
+            let passphrase = grin_util::ZeroingString::from(passphrase);
+
+            let seed = wallet
+                .lock()
+                .init(config, passphrase.clone(), true)?;
+
+            println!("{}", "Press ENTER when you have done so".bright_green().bold());
+
+            let mut line = String::new();
+            io::stdout().flush().unwrap();
+            io::stdin().read_line(&mut line).unwrap();
+
+            {
+                let mut wallet_inst = wallet.lock();
+                wallet_inst.complete(seed, config, "default", passphrase, true)?;
+                wallet_inst.update_tip_as_last_scanned()?;
+            }
+            show_address(config, wallet, false)?;
+
+            return Ok(());
+        }
+        Some("unlock") => {
+            let args = matches.subcommand_matches("unlock").unwrap();
+            let account = args.value_of("account").unwrap_or("default");
+            let passphrase = match args.is_present("passphrase") {
+                true => password_prompt(args.value_of("passphrase")),
+                false => "".to_string(),
+            };
+            *out_is_safe = args.value_of("passphrase").is_none();
+
+            {
+                let mut w = wallet.lock();
+                if !w.is_locked() {
+                    return Err(ErrorKind::WalletAlreadyUnlocked.into());
+                }
+                w.unlock(config, account, ZeroingString::from(passphrase.as_str()))?;
+            }
+
+            show_address(config, wallet.clone(), false)?;
+
+            start_wallet_api(config, wallet)?;
+
+            return Ok(());
+        }
+        Some("accounts") => {
+            let args = matches.subcommand_matches("accounts").unwrap();
+            wallet.lock().list_accounts(config.default_min_confirmations(), args.is_present("archived"))?;
+        }
+        Some("account") => {
+            let args = matches.subcommand_matches("account").unwrap();
+
+            let create_args = args.subcommand_matches("create");
+            let switch_args = args.subcommand_matches("switch");
+            let rename_args = args.subcommand_matches("rename");
+            if let Some(args) = create_args {
+                wallet
                     .lock()
                     .create_account(args.value_of("name").unwrap())?;
             } else if let Some(args) = switch_args {
@@ -1046,6 +2009,18 @@ fn do_command(
                 let old_account = args.value_of("old_account").unwrap();
                 let new_account = args.value_of("new_account").unwrap();
                 wallet.lock().rename_account(old_account, new_account)?;
+            } else if let Some(args) = args.subcommand_matches("archive") {
+                let account = args.value_of("name").unwrap();
+                wallet.lock().archive_account(account)?;
+                cli_message!("Account {} is archived", account);
+            } else if let Some(args) = args.subcommand_matches("delete") {
+                let account = args.value_of("name").unwrap();
+                wallet.lock().delete_account(account)?;
+                cli_message!("Account {} is deleted", account);
+            } else if let Some(args) = args.subcommand_matches("unarchive") {
+                let account = args.value_of("name").unwrap();
+                wallet.lock().unarchive_account(account)?;
+                cli_message!("Account {} is restored", account);
             }
 
             return Ok(());
@@ -1130,11 +2105,17 @@ fn do_command(
         Some("info") => {
             let args = matches.subcommand_matches("info").unwrap();
 
-            let confirmations = args.value_of("confirmations").unwrap_or("10");
-            let confirmations = u64::from_str_radix(confirmations, 10)
-                .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?;
+            let confirmations = match args.value_of("confirmations") {
+                Some(confirmations) => u64::from_str_radix(confirmations, 10)
+                    .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?,
+                None => config.default_min_confirmations(),
+            };
 
-            wallet.lock().info(!args.is_present("--no-refresh"), confirmations)?;
+            if args.is_present("all-accounts") {
+                wallet.lock().accounts_info(!args.is_present("--no-refresh"), confirmations, false)?;
+            } else {
+                wallet.lock().info(!args.is_present("--no-refresh"), confirmations)?;
+            }
         }
         Some("txs_count") => {
             let count = wallet.lock().txs_count()?;
@@ -1143,6 +2124,34 @@ fn do_command(
         Some("txs") => {
             let args = matches.subcommand_matches("txs").unwrap();
 
+            let parse_id = |args: &ArgMatches| -> Result<u32, Error> {
+                let id = args.value_of("id").unwrap();
+                Ok(id.parse::<u32>().map_err(|_| ErrorKind::InvalidTxId(id.to_string()))?)
+            };
+            if let Some(note_args) = args.subcommand_matches("note") {
+                wallet.lock().set_tx_note(parse_id(note_args)?, note_args.value_of("note").unwrap_or(""))?;
+                return Ok(());
+            } else if let Some(tag_args) = args.subcommand_matches("tag") {
+                let tags: Vec<String> = tag_args.values_of("tags").unwrap().map(|t| t.to_string()).collect();
+                wallet.lock().tag_tx(parse_id(tag_args)?, &tags, true)?;
+                return Ok(());
+            } else if let Some(untag_args) = args.subcommand_matches("untag") {
+                let tags: Vec<String> = untag_args.values_of("tags").unwrap().map(|t| t.to_string()).collect();
+                wallet.lock().tag_tx(parse_id(untag_args)?, &tags, false)?;
+                return Ok(());
+            } else if let Some(export_args) = args.subcommand_matches("export") {
+                let file_name = export_args.value_of("file").unwrap().replace("~", &home_dir);
+                let format = match export_args.value_of("format") {
+                    Some(f) => ExportFormat::from_str(f)?,
+                    None => ExportFormat::from_file_name(&file_name),
+                };
+                let account = export_args.value_of("account").map(|a| a.to_string());
+                let filter = parse_tx_filter(export_args, address_book.clone())?;
+                let count = wallet.lock().export_txs(account, &filter, &file_name, format)?;
+                cli_message!("{} transaction(s) exported to {}", count, file_name);
+                return Ok(());
+            }
+
             // get pagination parameters default is to not do pagination when length == 0.
             let pagination_length = args.value_of("length").unwrap_or("0");
             let pagination_start = args.value_of("offset").unwrap_or("0");
@@ -1178,7 +2187,44 @@ fn do_command(
                 None
             };
 
-            wallet.lock().txs(!no_refresh, show_full_info, pagination_start, pagination_length, tx_id, tx_slate_id )?;
+            let filter = parse_tx_filter(args, address_book.clone())?;
+
+            wallet.lock().txs(!no_refresh, show_full_info, pagination_start, pagination_length, tx_id, tx_slate_id, &filter )?;
+        }
+        Some("stats") => {
+            let args = matches.subcommand_matches("stats").unwrap();
+            wallet.lock().stats(!args.is_present("no-refresh"))?;
+        }
+        Some("statement") => {
+            let args = matches.subcommand_matches("statement").unwrap();
+            let from = parse_naive_date(args.value_of("from").unwrap())?;
+            let to = match args.value_of("to") {
+                Some(d) => parse_naive_date(d)?,
+                None => Utc::now().naive_utc().date(),
+            };
+            let interval = Interval::from_str(args.value_of("interval").unwrap_or("month"))?;
+            let account = args.value_of("account").map(|a| a.to_string());
+
+            let periods = wallet.lock().statement(account, from, to, interval)?;
+
+            match args.value_of("file") {
+                Some(file_name) => {
+                    let file_name = file_name.replace("~", &home_dir);
+                    let format = match args.value_of("format") {
+                        Some(f) => ExportFormat::from_str(f)?,
+                        None => ExportFormat::from_file_name(&file_name),
+                    };
+                    let data = match format {
+                        ExportFormat::Csv => statement::to_csv(&periods),
+                        ExportFormat::Json => serde_json::to_string_pretty(&periods)?,
+                    };
+                    let mut file = File::create(&file_name)
+                        .map_err(|e| ErrorKind::FileUnableToCreate(file_name.clone(), format!("{}", e)))?;
+                    file.write_all(data.as_bytes())?;
+                    cli_message!("Statement saved to {}", file_name);
+                }
+                None => statement::print_table(&periods),
+            }
         }
         Some("txs-bulk-validate") => {
             let args = matches.subcommand_matches("txs-bulk-validate").unwrap();
@@ -1205,6 +2251,27 @@ fn do_command(
         Some("outputs") => {
             let args = matches.subcommand_matches("outputs").unwrap();
 
+            if let Some(freeze_args) = args.subcommand_matches("freeze") {
+                let commit = freeze_args.value_of("commit").unwrap();
+                wallet.lock().freeze_output(commit, true)?;
+                cli_message!("Output {} is frozen", commit);
+                return Ok(());
+            } else if let Some(unfreeze_args) = args.subcommand_matches("unfreeze") {
+                let commit = unfreeze_args.value_of("commit").unwrap();
+                wallet.lock().freeze_output(commit, false)?;
+                cli_message!("Output {} is unfrozen", commit);
+                return Ok(());
+            } else if let Some(label_args) = args.subcommand_matches("label") {
+                let commit = label_args.value_of("commit").unwrap();
+                let label = label_args.value_of("label").unwrap_or("");
+                wallet.lock().label_output(commit, label)?;
+                match label.is_empty() {
+                    true => cli_message!("Label of the output {} is removed", commit),
+                    false => cli_message!("Output {} is labeled `{}`", commit, label),
+                }
+                return Ok(());
+            }
+
             // get pagination parameters default is to not do pagination when length == 0.
             let pagination_length = args.value_of("length").unwrap_or("0");
             let pagination_start = args.value_of("offset").unwrap_or("0");
@@ -1230,8 +2297,33 @@ fn do_command(
                 None
             };
 
-            let show_spent = args.is_present("show-spent");
-            wallet.lock().outputs(!no_refresh, show_spent, pagination_start, pagination_length)?;
+            let show_spent = args.is_present("show-spent");
+            wallet.lock().outputs(!no_refresh, show_spent, pagination_start, pagination_length)?;
+        }
+        Some("pending") => {
+            let args = matches.subcommand_matches("pending").unwrap();
+            wallet.lock().pending(!args.is_present("no-refresh"))?;
+        }
+        Some("wait") => {
+            let args = matches.subcommand_matches("wait").unwrap();
+            let id = match args.value_of("id") {
+                Some(id) => Some(id.parse::<u32>().map_err(|_| ErrorKind::InvalidTxId(id.to_string()))?),
+                None => None,
+            };
+            let slate_id = match args.value_of("txid") {
+                Some(txid) => Some(Uuid::parse_str(txid).map_err(|_| ErrorKind::InvalidTxId(txid.to_string()))?),
+                None => None,
+            };
+
+            let confirmations = args.value_of("confirmations").unwrap_or("1");
+            let confirmations = u64::from_str_radix(confirmations, 10)
+                .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?;
+
+            let timeout = args.value_of("timeout").unwrap_or("0");
+            let timeout = u64::from_str_radix(timeout, 10)
+                .map_err(|_| ErrorKind::GenericError(format!("invalid timeout `{}`", timeout)))?;
+
+            wait_for_confirmations(id, slate_id, confirmations, timeout, wallet)?;
         }
         Some("repost") => {
             let args = matches.subcommand_matches("repost").unwrap();
@@ -1248,7 +2340,14 @@ fn do_command(
             let id = id
                 .parse::<u32>()
                 .map_err(|_| ErrorKind::InvalidTxId(id.to_string()))?;
-            wallet.lock().cancel(id)?;
+            if args.is_present("safe") {
+                let timeout = args.value_of("timeout").unwrap_or("0");
+                let timeout = u64::from_str_radix(timeout, 10)
+                    .map_err(|_| ErrorKind::GenericError(format!("invalid timeout `{}`", timeout)))?;
+                safe_cancel(id, timeout, wallet)?;
+            } else {
+                wallet.lock().cancel(id)?;
+            }
         }
         Some("getnextkey") => {
             let args =  matches.subcommand_matches("getnextkey").unwrap();
@@ -1263,115 +2362,53 @@ fn do_command(
                 wallet.lock().getnextkey(amount)?;
             }
         }
-	Some("encryptslate") => {
-		let args = matches.subcommand_matches("encryptslate").unwrap();
- 		let to = args.value_of("to");
-		let slate = args.value_of("slate").unwrap();
-		let slate = Slate::deserialize_upgrade(&slate)?;
-
-		if to.is_none() {
-			return Err(ErrorKind::ToNotSpecified("".to_string()).into());
-		}
-		let to = to.unwrap();
-        let mwcmqs_address = MWCMQSAddress::from_str(&to.to_string())?;
-
-		if let Some((publisher, _)) = mwcmqs_broker {
-                        let slate = publisher.encrypt_slate(&slate, mwcmqs_address.borrow())?;
-			println!("slate='{}'", slate);
-		} else {
-            let address_pub_key = wallet.lock().get_payment_proof_address_pubkey()?;
-            let mwcmqs_address_for_publisher = config.get_mwcmqs_address(&address_pub_key)?;
-            let mwcmqs_secret_key = wallet.lock().get_payment_proof_address_secret()?;
-            let addr_name = mwcmqs_address.get_stripped();
-
-            let keychain_mask = Arc::new(Mutex::new(None));
-
-            let controller = grin_wallet_controller::controller::Controller::new(
-                &addr_name,
-                wallet.lock().get_wallet_instance()?,
-                keychain_mask,
-                config.max_auto_accept_invoice,
-                false,
-            );
-
-            let publisher = MWCMQPublisher::new(
-                mwcmqs_address_for_publisher,
-                &mwcmqs_secret_key,
-                config.clone().mwcmqs_domain.unwrap_or(DEFAULT_MWCMQS_DOMAIN.to_string()),
-                config.clone().mwcmqs_port.unwrap_or(DEFAULT_MWCMQS_PORT),
-                false,
-                Box::new(controller.clone())
-            );
-
-            let slate = publisher.encrypt_slate(&slate, mwcmqs_address.borrow())?;
-            println!("slate='{}'", slate);
-		}
-	}
-	Some("decryptslate") => {
-		let args = matches.subcommand_matches("decryptslate").unwrap();
-		let slate = args.value_of("slate").unwrap();
-        let public_key = wallet.lock().get_payment_proof_address_pubkey()?;
-		let source_address = ProvableAddress::from_pub_key(&public_key);
-		let mut data = "http://example.com/?".to_string();
-		data.push_str(slate);
-
-		let url = Url::parse(&data)?;
-		let mut pairs = url.query_pairs();
-		let mut from = String::new();
-		let mut signature = String::new();
-		let mut mapmessage = String::new();
-
-        while pairs.count()>0 {
-			let next = pairs.next();
-			let next = next.unwrap();
-			if next.0 == "from" { from = next.1.to_string(); }
-			if next.0 == "signature" { signature = next.1.to_string(); }
-			if next.0 == "mapmessage" { mapmessage = next.1.to_string(); }
-		}
-
- 		if let Some((publisher, _)) = mwcmqs_broker {
-            let decrypted_slate = publisher.decrypt_slate(from, mapmessage, signature, &source_address)?;
-			println!("slate='{}'", decrypted_slate);
-		}
-        else
-        {
-            let mwcmqs_address = config.get_mwcmqs_address(&public_key)?;
-            let mwcmqs_secret_key = wallet.lock().get_payment_proof_address_secret()?;
-            let addr_name = mwcmqs_address.get_stripped();
-
-            let keychain_mask = Arc::new(Mutex::new(None));
-
-            let controller = grin_wallet_controller::controller::Controller::new(
-                &addr_name,
-                wallet.lock().get_wallet_instance()?,
-                keychain_mask,
-                config.max_auto_accept_invoice,
-                false,
-            );
-
-            let publisher = MWCMQPublisher::new(
-                mwcmqs_address.clone(),
-                &mwcmqs_secret_key,
-                config.clone().mwcmqs_domain.unwrap_or(DEFAULT_MWCMQS_DOMAIN.to_string()),
-                config.clone().mwcmqs_port.unwrap_or(DEFAULT_MWCMQS_PORT),
-                false,
-                Box::new(controller.clone())
-            );
+        Some("encryptslate") => {
+            let args = matches.subcommand_matches("encryptslate").unwrap();
+            let slate = Slate::deserialize_upgrade(args.value_of("slate").unwrap())?;
+            let to = args.value_of("to")
+                .ok_or(ErrorKind::ToNotSpecified("".to_string()))?;
+            let mwcmqs_address = MWCMQSAddress::from_str(to)?;
+
+            let encrypted = match mwcmqs_broker {
+                Some((publisher, _)) => publisher.encrypt_slate(&slate, mwcmqs_address.borrow())?,
+                None => offline_publisher(config, &wallet.lock())?.encrypt_slate(&slate, mwcmqs_address.borrow())?,
+            };
+            match args.is_present("armor") {
+                true => print!("{}", slate_armor::armor(&ArmoredSlate::Encrypted(encrypted))),
+                false => println!("slate='{}'", encrypted),
+            }
+        }
+        Some("decryptslate") => {
+            let args = matches.subcommand_matches("decryptslate").unwrap();
+            // Armored slate is too long for the command line, it is read from the file
+            let query = match args.value_of("file") {
+                Some(file_name) => {
+                    let mut data = String::new();
+                    File::open(file_name.replace("~", &home_dir))?.read_to_string(&mut data)?;
+                    match slate_armor::dearmor(&data)? {
+                        ArmoredSlate::Encrypted(query) => query,
+                        ArmoredSlate::Plain(_) => return Err(ErrorKind::GenericError(format!("{} is not encrypted", file_name)).into()),
+                    }
+                }
+                None => args.value_of("slate")
+                    .ok_or(ErrorKind::GenericError("Please specify the slate or the armored slate file".to_string()))?
+                    .to_string(),
+            };
 
-            let decrypted_slate = publisher.decrypt_slate(from, mapmessage, signature, &source_address)?;
+            let w = wallet.lock();
+            let (_, decrypted_slate) = match mwcmqs_broker {
+                Some((publisher, _)) => decrypt_slate_query(publisher, &query, &w)?,
+                None => decrypt_slate_query(&offline_publisher(config, &w)?, &query, &w)?,
+            };
             println!("slate='{}'", decrypted_slate);
         }
-	}
         Some("receive") => {
             let args = matches.subcommand_matches("receive").unwrap();
             let key_id = args.value_of("key_id");
             let input = args.value_of("file").unwrap();
             let rfile_param = args.value_of("recv_file");
-            let mut file = File::open(input.replace("~", &home_dir))?;
-            let mut slate = String::new();
-            file.read_to_string(&mut slate)?;
-            let mut slate = Slate::deserialize_upgrade(&slate)?;
-            let mut file = File::create(&format!("{}.response", input.replace("~", &home_dir)))?;
+            let (mut slate, format) = read_slate_file(&input.replace("~", &home_dir), config, &wallet.lock())?;
+            let format = SlateFileFormat::from_args(args, format, address_book.clone())?;
 
             let output_amounts = if rfile_param.is_some() {
                 let mut nvec = Vec::new();
@@ -1401,7 +2438,7 @@ fn do_command(
 
             let w = wallet.lock();
             // Processing with a new receive account
-            w.process_sender_initiated_slate(Some(String::from("file")), &mut slate, key_id, output_amounts, None )?;
+            w.process_sender_initiated_slate(Some(String::from("file")), &mut slate, key_id, output_amounts, args.value_of("from-account") )?;
             let message = &slate.participant_data[0].message;
             let amount = core::amount_to_hr_string(slate.amount, false);
             if message.is_some() {
@@ -1411,29 +2448,13 @@ fn do_command(
                 cli_message!("{} received. amount = [{}]", input, amount);
             }
 
-            let out_slate = {
-                if slate.payment_proof.is_some() || slate.ttl_cutoff_height.is_some() {
-                    warn!("Transaction contains features that require mwc-wallet 3.0.0 or later");
-                    warn!("Please ensure the other party is running mwc-wallet v3.0.0 or later before sending");
-                    VersionedSlate::into_version(slate.clone(), SlateVersion::V3)
-                } else {
-                    let mut s = slate.clone();
-                    s.version_info.version = 2;
-                    s.version_info.orig_version = 2;
-                    VersionedSlate::into_version(s, SlateVersion::V2)
-                }
-           };
-
-            file.write_all(serde_json::to_string(&out_slate)?.as_bytes())?;
+            write_slate_file(&slate, &format!("{}.response", input.replace("~", &home_dir)), &format, config, &w)?;
             cli_message!("{}.response created successfully.", input);
         }
         Some("showpubkeys") => {
             let args = matches.subcommand_matches("showpubkeys").unwrap();
             let input = args.value_of("file").unwrap();
-            let mut file = File::open(input.replace("~", &home_dir))?;
-            let mut slate = String::new();
-            file.read_to_string(&mut slate)?;
-            let slate = Slate::deserialize_upgrade(&slate)?;
+            let (slate, _) = read_slate_file(&input.replace("~", &home_dir), config, &wallet.lock())?;
             for p in slate.participant_data {
                 println!("pubkey[{}]={:?}", p.id, p.public_blind_excess);
             }
@@ -1442,14 +2463,26 @@ fn do_command(
             let args = matches.subcommand_matches("finalize").unwrap();
             let fluff = args.is_present("fluff");
             let input = args.value_of("file").unwrap();
-            let mut file = File::open(input.replace("~", &home_dir))?;
-            let mut slate = String::new();
-            file.read_to_string(&mut slate)?;
-            let mut slate = Slate::deserialize_upgrade(&slate)?;
+            let (mut slate, _) = read_slate_file(&input.replace("~", &home_dir), config, &wallet.lock())?;
             if &slate.participant_data.len() -1 ==0 {
                 cli_message!("Not a valid response file!");
             } else {
-                wallet.lock().finalize_post_slate(&mut slate, fluff)?;
+                let w = wallet.lock();
+                // Invoice issuer has the receive transaction for the slate
+                let is_invoice = w.get_tx_with_height(false, None, Some(slate.id))
+                    .map(|(tx, _)| tx.tx_type == TxLogEntryType::TxReceived)
+                    .unwrap_or(false);
+                if is_invoice {
+                    let original_slate = w.get_invoice_slate(&slate.id)?
+                        .ok_or(ErrorKind::GenericError(format!("Invoice slate {} is not found, unable to verify the response", slate.id)))?;
+                    // Payer can change that, restoring original value
+                    slate.ttl_cutoff_height = original_slate.ttl_cutoff_height.clone();
+                    // Checking the payer didn't do any harm to slate
+                    Slate::compare_slates_invoice(&original_slate, &slate)?;
+                    // Do exactly what the mwcmqs invoice does
+                    w.tx_lock_outputs(&slate, Some(String::from("file")), 0)?;
+                }
+                w.finalize_post_slate(&mut slate, fluff)?;
                 cli_message!("{} finalized.", input);
             }
         }
@@ -1486,35 +2519,15 @@ fn do_command(
         Some("send") => {
             let args = matches.subcommand_matches("send").unwrap();
             let to = args.value_of("to");
-            let expected_proof_address = args.value_of("expectedproof");
             let input = args.value_of("file");
+            let group = args.value_of("group");
             let message = args.value_of("message").map(|s| s.to_string());
-            let apisecret = args.value_of("apisecret").map(|s| s.to_string());
-            let strategy = args.value_of("strategy").unwrap_or("smallest");
-            if strategy != "smallest" && strategy != "all" && strategy != "custom" {
-                return Err(ErrorKind::InvalidStrategy.into());
-            }
+            let (strategy, output_list) = parse_selection_strategy(args)?;
+            let strategy = strategy.as_str();
 
             let routputs_arg = args.value_of("routputs").unwrap_or("1");
             let routputs = usize::from_str_radix(routputs_arg, 10)?;
 
-            let outputs_arg = args.value_of("outputs");
-
-            let output_list : Option<Vec<String>> = if outputs_arg.is_none() {
-                if strategy == "custom" {
-                    return Err(ErrorKind::CustomWithNoOutputs.into());
-                }
-                None
-            }
-            else
-            {
-                if strategy != "custom" {
-                    return Err(ErrorKind::NonCustomWithOutputs.into());
-                }
-                let ret: Vec<String> = outputs_arg.unwrap().split(",").map(|s| s.to_string()).collect();
-                Some(ret)
-            };
-
             let ttl_blocks = args.value_of("ttl-blocks").unwrap_or("0");
             let ttl_blocks = u64::from_str_radix(ttl_blocks, 10)
                 .map_err(|_| ErrorKind::InvalidTTLBlocks(ttl_blocks.to_string()))?;
@@ -1539,14 +2552,56 @@ fn do_command(
             let fluff = args.is_present("fluff");
             let do_proof = args.is_present("proof");
 
-            let amount = args.value_of("amount").unwrap();
+            let send_params = SendParams {
+                confirmations,
+                strategy: strategy.to_string(),
+                change_outputs,
+                message,
+                output_list,
+                version,
+                ttl_blocks,
+                fluff,
+                do_proof,
+                apisecret: args.value_of("apisecret").map(|s| s.to_string()),
+                expected_proof_address: args.value_of("expectedproof").map(|s| s.to_string()),
+                from_account: args.value_of("from-account").map(|s| s.to_string()),
+            };
+
+            if let Some(group) = group {
+                let continue_on_error = args.is_present("continue-on-error");
+                let recipients = group_recipients(
+                    group,
+                    args.value_of("amount"),
+                    args.value_of("amounts").map(|f| f.replace("~", &home_dir)),
+                    address_book.clone(),
+                )?;
+                return send_to_group(
+                    group,
+                    recipients,
+                    continue_on_error,
+                    &send_params,
+                    config,
+                    wallet,
+                    address_book,
+                );
+            }
+
+            let amount = args.value_of("amount")
+                .ok_or(ErrorKind::GenericError("Please specify the amount to send".to_string()))?;
             let mut ntotal = 0;
             if amount == "ALL" {
-                // Update from the node once. No reasons to do that twice in tthe row
-                let max_available = wallet.lock().output_count(true, confirmations, &output_list)?;
-                let total_value = wallet.lock().total_value(false, confirmations, &output_list)?;
-                let fee = tx_fee(max_available, 1, 1, None);
-                ntotal = if total_value >= fee { total_value - fee } else { 0 };
+                // Estimate the transaction that spends everything the strategy allows without change. The real one
+                // selects the same inputs, and libwallet doesn't add the change when the inputs pay the amount and
+                // the fee exactly.
+                let estimate = wallet.lock().estimate_send_all_tx(
+                    send_params.from_account.clone(),
+                    confirmations,
+                    strategy,
+                    send_params.output_list.clone(),
+                    routputs,
+                    &None,
+                )?;
+                ntotal = estimate.amount.saturating_sub(estimate.fee);
             }
 
             let amount = match amount == "ALL" {
@@ -1554,152 +2609,285 @@ fn do_command(
                 false => core::amount_from_hr_string(amount).map_err(|_| ErrorKind::InvalidAmount(amount.to_string()))?,
             };
 
-            // Preparign for sync update progress printing
-            let running = Arc::new( AtomicBool::new(true) );
-            let (tx, rx) = mpsc::channel();
-            // Starting printing to console thread.
-            let updater = grin_wallet_libwallet::api_impl::owner_updater::start_updater_console_thread(rx, running.clone())?;
-            let status_send_channel = Some(tx);
+            if args.is_present("estimate") {
+                let estimate = with_status_updater(|status_send_channel| {
+                    wallet.lock().estimate_send_tx(
+                        send_params.from_account.clone(),
+                        amount,
+                        confirmations,
+                        strategy,
+                        change_outputs,
+                        500,
+                        send_params.output_list.clone(),
+                        routputs,
+                        status_send_channel,
+                    )
+                })?;
+                print_send_estimate(amount, &estimate, change_outputs);
+                return Ok(());
+            }
 
             // Store slate in a file
             if let Some(input) = input {
-                let mut file = File::create(input.replace("~", &home_dir))?;
-                let w = wallet.lock();
-                let mut address = Some(String::from("file"));
-                if do_proof {
-                    address = Some(String::from("file_proof"));
-                }
+                let format = SlateFileFormat::from_args(args, SlateFileFormat::Json, address_book.clone())?;
+                with_status_updater(|status_send_channel| {
+                    let w = wallet.lock();
+                    let mut address = Some(String::from("file"));
+                    if do_proof {
+                        address = Some(String::from("file_proof"));
+                    }
 
-                let slate = w.initiate_send_tx(
-                    address.clone(),
-                    amount,
-                    confirmations,
-                    strategy,
-                    change_outputs,
-                    500,
-                    message,
-                    output_list,
-                    version,
-                    routputs,
-                    &status_send_channel,
-                    ttl_blocks,
-                    false,
-                )?;
+                    let slate = w.initiate_send_tx(
+                        send_params.from_account.clone(),
+                        address.clone(),
+                        amount,
+                        confirmations,
+                        strategy,
+                        change_outputs,
+                        500,
+                        send_params.message.clone(),
+                        send_params.output_list.clone(),
+                        version,
+                        routputs,
+                        status_send_channel,
+                        ttl_blocks,
+                        false,
+                    )?;
+
+                    write_slate_file(&slate, &input.replace("~", &home_dir), &format, config, &w)?;
+
+                    w.tx_lock_outputs(
+                        &slate,
+                        address,
+                        0)
+                })?;
 
-                let out_slate = {
-                        if slate.payment_proof.is_some() || slate.ttl_cutoff_height.is_some() {
-                                warn!("Transaction contains features that require mwc-wallet 3.0.0 or later");
-                                warn!("Please ensure the other party is running mwc-wallet v3.0.0 or later before sending");
-                                VersionedSlate::into_version(slate.clone(), SlateVersion::V3)
-                        } else {
-                                let mut s = slate.clone();
-                                s.version_info.version = 2;
-                                s.version_info.orig_version = 2;
-                                VersionedSlate::into_version(s, SlateVersion::V2)
-                        }
-                };
+                cli_message!("{} created successfully.", input);
+                return Ok(());
+            }
 
-                file.write_all(serde_json::to_string(&out_slate)?.as_bytes())?;
+            let (to, display_to) = resolve_address(to.unwrap(), address_book.clone())?;
 
-                w.tx_lock_outputs(
-                    &slate,
-                    address,
-                    0)?;
+            let (slate, ret_id) = with_status_updater(|status_send_channel| {
+                send_to_address(&wallet.lock(), config, &to, amount, &send_params, status_send_channel)
+            })?;
+            cli_message!(	
+                    "Transaction [{}] for [{}] MWCs sent successfully to [{}]",	
+                slate.id.to_string(),	
+                core::amount_to_hr_string(slate.amount, false),	
+                display_to
+            );
+            println!("txid={:?}", ret_id);
+        }
+        Some("consolidate") => {
+            let args = matches.subcommand_matches("consolidate").unwrap();
+
+            let min_outputs = args.value_of("min-outputs").unwrap_or("10");
+            let min_outputs = usize::from_str_radix(min_outputs, 10)
+                .map_err(|_| ErrorKind::InvalidNumOutputs(min_outputs.to_string()))?;
+
+            let max_per_tx = args.value_of("max-per-tx").unwrap_or("100");
+            let max_per_tx = usize::from_str_radix(max_per_tx, 10)
+                .map_err(|_| ErrorKind::InvalidNumOutputs(max_per_tx.to_string()))?;
+            if max_per_tx < 2 || max_per_tx > 500 {
+                return Err(ErrorKind::GenericError("--max-per-tx must be between 2 and 500".to_string()).into());
+            }
 
-                cli_message!("{} created successfully.", input);
+            let confirmations = args.value_of("confirmations").unwrap_or("10");
+            let confirmations = u64::from_str_radix(confirmations, 10)
+                .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?;
+            if confirmations < 1 {
+                return Err(ErrorKind::ZeroConfNotAllowed.into());
+            }
 
-                // Stopping updater, sync should be done by now
-                running.store(false, Ordering::Relaxed);
-                let _ = updater.join();
+            let below = match args.value_of("below") {
+                Some(b) => Some(core::amount_from_hr_string(b).map_err(|_| ErrorKind::InvalidAmount(b.to_string()))?),
+                None => None,
+            };
 
-                return Ok(());
-            }
+            // Every output of the batch is spent, so there is no change and the fee is estimated without it
+            let send_params = SendParams {
+                confirmations,
+                strategy: "custom".to_string(),
+                change_outputs: 0,
+                message: None,
+                output_list: None,
+                version: None,
+                ttl_blocks: 0,
+                fluff: args.is_present("fluff"),
+                do_proof: false,
+                apisecret: None,
+                expected_proof_address: None,
+                from_account: None,
+            };
 
-            let mut to = to.unwrap().to_string();
-            let mut display_to = None;
+            consolidate(min_outputs, max_per_tx, below, args.is_present("dry-run"), args.is_present("yes"), &send_params, wallet)?;
+        }
+        Some("split") => {
+            let args = matches.subcommand_matches("split").unwrap();
+
+            let parse_amount = |amount: &str| -> Result<u64, Error> {
+                let amount = core::amount_from_hr_string(amount)
+                    .map_err(|_| ErrorKind::InvalidAmount(amount.to_string()))?;
+                if amount == 0 {
+                    return Err(ErrorKind::InvalidAmount("0".to_string()).into());
+                }
+                Ok(amount)
+            };
 
-            if to.starts_with("@") {
-                let contact = address_book.lock().get_contact(&to[1..])?;
-                to = contact.get_address().to_string();
-                display_to = Some(contact.get_name().to_string());
-            }
-            // try parse as a general address and fallback to mwcmqs address
-            let address = Address::parse(&to);
-            let address: Result<Box<dyn Address>, Error> = match address {
-                Ok(address) => Ok(address),
-                Err(e) => {
-                    Ok(Box::new(MWCMQSAddress::from_str(&to).map_err(|_| e)?) as Box<dyn Address>)
+            let amounts: Vec<u64> = match args.value_of("amounts") {
+                Some(amounts) => amounts
+                    .split(",")
+                    .map(|a| parse_amount(a.trim()))
+                    .collect::<Result<Vec<u64>, Error>>()?,
+                None => {
+                    let count = args.value_of("count")
+                        .ok_or(ErrorKind::GenericError("Please specify --amounts or --count with --each".to_string()))?;
+                    let count = usize::from_str_radix(count, 10)
+                        .map_err(|_| ErrorKind::InvalidNumOutputs(count.to_string()))?;
+                    let each = args.value_of("each")
+                        .ok_or(ErrorKind::GenericError("Please specify the amount of every output with --each".to_string()))?;
+                    vec![parse_amount(each)?; count]
                 }
             };
+            if amounts.is_empty() || amounts.len() > 500 {
+                return Err(ErrorKind::InvalidNumOutputs(amounts.len().to_string()).into());
+            }
+            let amount: u64 = amounts.iter().sum();
 
-            let to = address?;
-            if display_to.is_none() {	
-                display_to = Some(to.get_stripped());	
+            let confirmations = args.value_of("confirmations").unwrap_or("10");
+            let confirmations = u64::from_str_radix(confirmations, 10)
+                .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?;
+            if confirmations < 1 {
+                return Err(ErrorKind::ZeroConfNotAllowed.into());
             }
 
-            let w = wallet.lock();
-            let address = Some(to.to_string());
-            let mut slate = w.initiate_send_tx(
-                address.clone(),
-                amount,
+            let ttl_blocks = args.value_of("ttl-blocks").unwrap_or("0");
+            let ttl_blocks = u64::from_str_radix(ttl_blocks, 10)
+                .map_err(|_| ErrorKind::InvalidTTLBlocks(ttl_blocks.to_string()))?;
+
+            // The new outputs are received into the same account
+            let from_account = args.value_of("from-account");
+
+            let send_params = SendParams {
                 confirmations,
-                strategy,
-                change_outputs,
-                500,
-                message,
-                output_list,
-                version,
-                1,
-                &status_send_channel,
+                strategy: "smallest".to_string(),
+                change_outputs: 1,
+                message: None,
+                output_list: None,
+                version: None,
                 ttl_blocks,
-                do_proof,
-            )?;
+                fluff: args.is_present("fluff"),
+                do_proof: false,
+                apisecret: None,
+                expected_proof_address: None,
+                from_account: from_account.map(|s| s.to_string()),
+            };
+
+            let (slate, ret_id) = with_status_updater(|status_send_channel| {
+                self_send(&wallet.lock(), amount, &send_params, from_account, amounts.len(), Some(amounts.clone()), status_send_channel)
+            })?;
+            cli_message!(
+                "Split transaction [{}] for [{}] MWCs into {} outputs posted, tx id: {}",
+                slate.id.to_string(),
+                core::amount_to_hr_string(amount, false),
+                amounts.len(),
+                ret_id
+            );
+        }
+        Some("transfer") => {
+            let args = matches.subcommand_matches("transfer").unwrap();
+            let from = args.value_of("from").unwrap();
+            let to = args.value_of("to").unwrap();
+            if from == to {
+                return Err(ErrorKind::GenericError("Source and destination accounts must be different".to_string()).into());
+            }
+            let amount = args.value_of("amount").unwrap();
+            let amount = core::amount_from_hr_string(amount)
+                .map_err(|_| ErrorKind::InvalidAmount(amount.to_string()))?;
 
-            // Stopping updater, sync should be done by now
-            running.store(false, Ordering::Relaxed);
-            let _ = updater.join();
+            let confirmations = args.value_of("confirmations").unwrap_or("10");
+            let confirmations = u64::from_str_radix(confirmations, 10)
+                .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?;
+            if confirmations < 1 {
+                return Err(ErrorKind::ZeroConfNotAllowed.into());
+            }
 
-            let method = match to.address_type() {
-                AddressType::MWCMQS => "mwcmqs",
-                AddressType::Https => "http",
+            let send_params = SendParams {
+                confirmations,
+                strategy: "smallest".to_string(),
+                change_outputs: 1,
+                message: args.value_of("message").map(|s| s.to_string()),
+                output_list: None,
+                version: None,
+                ttl_blocks: 0,
+                fluff: args.is_present("fluff"),
+                do_proof: false,
+                apisecret: None,
+                expected_proof_address: None,
+                from_account: Some(from.to_string()),
             };
 
-            let original_slate = slate.clone();
+            let (slate, ret_id) = with_status_updater(|status_send_channel| {
+                self_send(&wallet.lock(), amount, &send_params, Some(to), 1, None, status_send_channel)
+            })?;
+            cli_message!(
+                "Transfer [{}] of [{}] MWCs from account '{}' to account '{}' posted, tx id: {}",
+                slate.id.to_string(),
+                core::amount_to_hr_string(amount, false),
+                from,
+                to,
+                ret_id
+            );
+        }
+        Some("send-batch") => {
+            let args = matches.subcommand_matches("send-batch").unwrap();
+            let csv_file = args.value_of("file").unwrap().replace("~", &home_dir);
 
-            let sender = grin_wallet_impls::create_sender(method, &to.to_string(), &apisecret, Some(config.get_tor_config()))?;
-            slate = sender.send_tx(&slate)?;
-            //check the expected proof address
-            //compare the expected listening wallet proof address  to the value the returned slate. If they don't match, return error
-            if let Some(expected_addr) = expected_proof_address {
-                if let Some(ref p) = slate.payment_proof {
-                    let receiver_a = p.clone().receiver_address;
-                    if receiver_a.public_key.len() ==56 &&  receiver_a.public_key!= expected_addr {
-                        return Err(ErrorKind::ProofAddresMismatch(receiver_a.public_key, expected_addr.to_string()).into());
-                    }
-                }
-            }
+            let ttl_blocks = args.value_of("ttl-blocks").unwrap_or("0");
+            let ttl_blocks = u64::from_str_radix(ttl_blocks, 10)
+                .map_err(|_| ErrorKind::InvalidTTLBlocks(ttl_blocks.to_string()))?;
 
+            let confirmations = args.value_of("confirmations").unwrap_or("10");
+            let confirmations = u64::from_str_radix(confirmations, 10)
+                .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?;
+            if confirmations < 1 {
+                return Err(ErrorKind::ZeroConfNotAllowed.into());
+            }
 
-            // Sender can change that, restoring original value
-            slate.ttl_cutoff_height = original_slate.ttl_cutoff_height.clone();
-            // Checking is sender didn't do any harm to slate
-            Slate::compare_slates_send( &original_slate, &slate)?;
+            let change_outputs = args.value_of("change-outputs").unwrap_or("1");
+            let change_outputs = u32::from_str_radix(change_outputs, 10)
+                .map_err(|_| ErrorKind::InvalidNumOutputs(change_outputs.to_string()))?;
 
-            w.tx_lock_outputs(&slate, address,0)?;
-            w.finalize_post_slate( &mut slate, fluff)?;
+            let send_params = SendParams {
+                confirmations,
+                strategy: "smallest".to_string(),
+                change_outputs,
+                message: None,
+                output_list: None,
+                version: None,
+                ttl_blocks,
+                fluff: args.is_present("fluff"),
+                do_proof: args.is_present("proof"),
+                apisecret: None,
+                expected_proof_address: None,
+                from_account: None,
+            };
 
-            let ret_id = w.get_id(slate.id)?;
-            cli_message!(	
-                    "Transaction [{}] for [{}] MWCs sent successfully to [{}]",	
-                slate.id.to_string(),	
-                core::amount_to_hr_string(slate.amount, false),	
-                display_to.unwrap()	
-            );
-            println!("txid={:?}", ret_id);
+            send_batch(
+                &csv_file,
+                args.is_present("dry-run"),
+                args.is_present("continue-on-error"),
+                args.is_present("resume"),
+                args.is_present("new"),
+                &send_params,
+                config,
+                wallet,
+                address_book,
+            )?;
         }
         Some("invoice") => {
             let args = matches.subcommand_matches("invoice").unwrap();
-            let to = args.value_of("to").unwrap();
             let outputs = args.value_of("outputs").unwrap_or("1");
             let outputs = usize::from_str_radix(outputs, 10)
                 .map_err(|_| ErrorKind::InvalidNumOutputs(outputs.to_string()))?;
@@ -1707,6 +2895,25 @@ fn do_command(
             let amount = core::amount_from_hr_string(amount)
                 .map_err(|_| ErrorKind::InvalidAmount(amount.to_string()))?;
             let fluff = args.is_present("fluff");
+            let from_account = args.value_of("from-account").map(|s| s.to_string());
+
+            // Store the invoice slate in a file. The payer returns the response for `finalize`.
+            if let Some(file_name) = args.value_of("file") {
+                let format = SlateFileFormat::from_args(args, SlateFileFormat::Json, address_book.clone())?;
+                let w = wallet.lock();
+                let slate = w.initiate_receive_tx(from_account, Some(String::from("file")), amount, outputs)?;
+                w.store_invoice_slate(&slate)?;
+                write_slate_file(&slate, &file_name.replace("~", &home_dir), &format, config, &w)?;
+                cli_message!(
+                    "invoice slate [{}] for [{}] MWCs created successfully at {}",
+                    slate.id.to_string(),
+                    core::amount_to_hr_string(slate.amount, false),
+                    file_name
+                );
+                return Ok(());
+            }
+
+            let to = args.value_of("to").unwrap();
 
             let mut to = to.to_string();
             let mut display_to = None;
@@ -1730,7 +2937,7 @@ fn do_command(
                 display_to = Some(to.get_stripped());
             }
 
-            let mut slate = wallet.lock().initiate_receive_tx(Some(to.to_string()) ,amount, outputs)?;
+            let mut slate = wallet.lock().initiate_receive_tx(from_account, Some(to.to_string()) ,amount, outputs)?;
 
             let method = match to.address_type() {
                 AddressType::MWCMQS => "mwcmqs",
@@ -1760,6 +2967,127 @@ fn do_command(
                 display_to.unwrap()
             );
         }
+        Some("invoices") => {
+            let args = matches.subcommand_matches("invoices").unwrap();
+            let parse_id = |args: &ArgMatches| -> Result<u32, Error> {
+                let id = args.value_of("id").unwrap();
+                Ok(id.parse::<u32>().map_err(|_| ErrorKind::GenericError(format!("invalid invoice id `{}`", id)))?)
+            };
+
+            if let Some(approve_args) = args.subcommand_matches("approve") {
+                let publisher = match mwcmqs_broker {
+                    Some((publisher, _)) => publisher,
+                    None => return Err(ErrorKind::GenericError("mwcmqs listener is not running, start it with `listen`".to_string()).into()),
+                };
+                let confirmations = approve_args.value_of("confirmations").unwrap_or("10");
+                let confirmations = u64::from_str_radix(confirmations, 10)
+                    .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?;
+                if confirmations < 1 {
+                    return Err(ErrorKind::ZeroConfNotAllowed.into());
+                }
+                let from_account = approve_args.value_of("from-account").map(|s| s.to_string());
+                let (strategy, output_list) = parse_selection_strategy(approve_args)?;
+                let id = parse_id(approve_args)?;
+
+                let w = wallet.lock();
+                let invoice = w.get_queued_invoice(id, config.invoice_expiry_hours)?;
+                let to = Address::parse(&invoice.from)?;
+                let slate: Slate = invoice.slate.clone().into();
+                // Same path as the paid mwcmqs invoice: add inputs, lock them and return the slate to the issuer
+                let slate = w.process_invoice(from_account, Some(to.to_string()), &slate, confirmations, &strategy, output_list, 1)?;
+                if let Err(e) = publisher.post_slate(&versioned_slate(&slate), to.borrow()) {
+                    // Inputs are locked already, a retry would lock another set of them
+                    if let Err(cancel_err) = w.cancel_slate(slate.id) {
+                        cli_message!("Unable to cancel transaction [{}], its outputs stay locked. {}", slate.id, cancel_err);
+                    }
+                    return Err(e.into());
+                }
+                w.remove_queued_invoice(id)?;
+                cli_message!(
+                    "invoice [{}] for [{}] MWCs is paid and sent back to [{}]",
+                    id,
+                    core::amount_to_hr_string(slate.amount, false),
+                    to.get_stripped()
+                );
+            } else if let Some(reject_args) = args.subcommand_matches("reject") {
+                let id = parse_id(reject_args)?;
+                wallet.lock().remove_queued_invoice(id)?;
+                cli_message!("invoice [{}] is rejected", id);
+            } else {
+                wallet.lock().list_queued_invoices(config.invoice_expiry_hours)?;
+            }
+        }
+        Some("request") => {
+            let args = matches.subcommand_matches("request").unwrap();
+            let mwcmqs_address = || -> Result<String, Error> {
+                let address_pub_key = wallet.lock().get_payment_proof_address_pubkey()?;
+                Ok(config.get_mwcmqs_address(&address_pub_key)?.get_stripped())
+            };
+
+            if let Some(create_args) = args.subcommand_matches("create") {
+                let amount = create_args.value_of("amount").unwrap();
+                let amount = core::amount_from_hr_string(amount).map_err(|_| ErrorKind::InvalidAmount(amount.to_string()))?;
+                let memo = create_args.value_of("memo").map(|m| m.to_string());
+                let expires = match create_args.value_of("expires") {
+                    Some(e) => Some(payment_requests::parse_expiry(e)?),
+                    None => None,
+                };
+                let request = wallet.lock().create_payment_request(amount, memo, expires)?;
+                payment_requests::print_details(&request, &mwcmqs_address()?);
+            } else if let Some(show_args) = args.subcommand_matches("show") {
+                let id = show_args.value_of("id").unwrap();
+                let id = id.parse::<u32>().map_err(|_| ErrorKind::GenericError(format!("invalid request id `{}`", id)))?;
+                let request = wallet.lock().get_payment_request(id, !show_args.is_present("no-refresh"))?;
+                payment_requests::print_details(&request, &mwcmqs_address()?);
+            } else {
+                let refresh = !args.subcommand_matches("list").map(|a| a.is_present("no-refresh")).unwrap_or(false);
+                wallet.lock().list_payment_requests(refresh)?;
+            }
+        }
+        Some("pay") => {
+            let args = matches.subcommand_matches("pay").unwrap();
+            let input = args.value_of("file").unwrap();
+            let confirmations = args.value_of("confirmations").unwrap_or("10");
+            let confirmations = u64::from_str_radix(confirmations, 10)
+                .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?;
+            if confirmations < 1 {
+                return Err(ErrorKind::ZeroConfNotAllowed.into());
+            }
+            let change_outputs = args.value_of("change-outputs").unwrap_or("1");
+            let change_outputs = u32::from_str_radix(change_outputs, 10)
+                .map_err(|_| ErrorKind::InvalidNumOutputs(change_outputs.to_string()))?;
+            let from_account = args.value_of("from-account").map(|s| s.to_string());
+            let (strategy, output_list) = parse_selection_strategy(args)?;
+
+            let (slate, format) = read_slate_file(&input.replace("~", &home_dir), config, &wallet.lock())?;
+            let format = SlateFileFormat::from_args(args, format, address_book.clone())?;
+            if slate.participant_data.len() != 1 || slate.tx.body.outputs.is_empty() || !slate.tx.body.inputs.is_empty() {
+                return Err(ErrorKind::GenericError(format!("{} is not an invoice slate", input)).into());
+            }
+
+            cli_message!("Invoice {}", slate.id);
+            cli_message!("    amount:  {} MWC", core::amount_to_hr_string(slate.amount, false));
+            if let Some(message) = &slate.participant_data[0].message {
+                cli_message!("    message: {}", message);
+            }
+            match slate.payment_proof.as_ref() {
+                Some(proof) => cli_message!("    from:    {}", proof.receiver_address.public_key),
+                None => cli_message!("    from:    unknown, the invoice has no payment proof address"),
+            }
+            if !args.is_present("yes") && !prompt_yes("Pay this invoice?")? {
+                cli_message!("Invoice is not paid");
+                return Ok(());
+            }
+
+            let w = wallet.lock();
+            let slate = w.process_invoice(from_account, Some(String::from("file")), &slate, confirmations, &strategy, output_list, change_outputs)?;
+            write_slate_file(&slate, &format!("{}.response", input.replace("~", &home_dir)), &format, config, &w)?;
+            cli_message!(
+                "invoice paid, fee {} MWC. Return {}.response to the issuer to finalize it.",
+                core::amount_to_hr_string(slate.fee, false),
+                input
+            );
+        }
         Some("restore") => {
             *out_is_safe = false;
             let args = matches.subcommand_matches("restore").unwrap();
@@ -1874,6 +3202,7 @@ fn do_command(
             let args = matches.subcommand_matches("set-recv").unwrap();
             let account = args.value_of("account").unwrap();
             if wallet.lock().account_path(account)?.is_some() {
+                wallet.lock().check_receive_account(Some(account))?;
                 grin_wallet_libwallet::set_receive_account(account.to_string());
                 cli_message!("Incoming funds will be received in account: {}", account);
             }
@@ -0,0 +1,74 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use grin_wallet_libwallet::AcctPathMapping;
+use common::{json_store, Error, ErrorKind};
+
+const ACCOUNT_ARCHIVE_FILE: &'static str = "account_archive.json";
+
+/// Archived and deleted accounts, identified by the BIP-32 path so renames don't lose them.
+/// libwallet can't remove an account, so a deleted account is only hidden and can be restored.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AccountArchive {
+    pub archived: BTreeSet<String>,
+    pub deleted: BTreeSet<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+fn acct_key(acct: &AcctPathMapping) -> String {
+    acct.path.to_bip_32_string()
+}
+
+impl AccountArchive {
+    /// Load the archive from the wallet data dir. Empty if nothing was stored yet.
+    pub fn open(data_dir: &str) -> Result<Self, Error> {
+        let path = Path::new(data_dir).join(ACCOUNT_ARCHIVE_FILE);
+        let mut archive: AccountArchive = json_store::load(&path, "account archive")?
+            .unwrap_or_default();
+        archive.path = path;
+        Ok(archive)
+    }
+
+    pub fn is_archived(&self, acct: &AcctPathMapping) -> bool {
+        self.archived.contains(&acct_key(acct))
+    }
+
+    pub fn is_deleted(&self, acct: &AcctPathMapping) -> bool {
+        self.deleted.contains(&acct_key(acct))
+    }
+
+    /// Archived or deleted account, it is not listed and can't receive
+    pub fn is_hidden(&self, acct: &AcctPathMapping) -> bool {
+        self.is_archived(acct) || self.is_deleted(acct)
+    }
+
+    /// Error if the account is archived or deleted
+    pub fn check_can_receive(&self, acct: &AcctPathMapping) -> Result<(), Error> {
+        match self.is_hidden(acct) {
+            true => Err(ErrorKind::AccountArchived(acct.label.clone()).into()),
+            false => Ok(()),
+        }
+    }
+
+    pub fn archive(&mut self, acct: &AcctPathMapping) -> Result<(), Error> {
+        self.archived.insert(acct_key(acct));
+        self.save()
+    }
+
+    pub fn delete(&mut self, acct: &AcctPathMapping) -> Result<(), Error> {
+        self.archived.remove(&acct_key(acct));
+        self.deleted.insert(acct_key(acct));
+        self.save()
+    }
+
+    pub fn unarchive(&mut self, acct: &AcctPathMapping) -> Result<(), Error> {
+        self.archived.remove(&acct_key(acct));
+        self.deleted.remove(&acct_key(acct));
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        json_store::save(&self.path, self)
+    }
+}
@@ -14,7 +14,7 @@ use grin_wallet_libwallet::proof::tx_proof::TxProof;
 use grin_wallet_libwallet::proof::proofaddress;
 use grin_wallet_libwallet::{AcctPathMapping, NodeClient, Slate, TxLogEntry,
                             WalletInfo, OutputCommitMapping, WalletInst, WalletLCProvider,
-                            StatusMessage, TxLogEntryType, OutputData};
+                            StatusMessage, TxLogEntryType, OutputData, WalletBackend};
 use grin_wallet_libwallet::api_impl::types::SwapStartArgs;
 use grin_core::core::Transaction;
 use grin_keychain::{Identifier};
@@ -250,6 +250,212 @@ pub fn retrieve_outputs<'a, L, C, K>(
     res
 }
 
+// Parent key of the account, None means the active account
+fn account_parent_key<'a, T: ?Sized, C, K>(w: &mut T, account: Option<String>) -> Result<Identifier, Error>
+    where
+        T: WalletBackend<'a, C, K>,
+        C: NodeClient + 'a,
+        K: Keychain + 'a,
+{
+    match account {
+        Some(label) => Ok(keys::accounts(w)?
+            .into_iter()
+            .find(|a| a.label == label)
+            .map(|a| a.path)
+            .ok_or(ErrorKind::GenericError(format!("Account `{}` not found", label)))?),
+        None => Ok(w.parent_key_id()),
+    }
+}
+
+// Outputs of the account. None means the active account.
+pub fn retrieve_account_outputs<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    account: Option<String>,
+    include_spent: bool,
+) -> Result<Vec<OutputCommitMapping>, Error>
+    where
+        L: WalletLCProvider<'a, C, K>,
+        C: NodeClient + 'a,
+        K: Keychain + 'a,
+{
+    wallet_lock!(wallet_inst, w);
+    let parent_key_id = account_parent_key(&mut **w, account)?;
+
+    let outputs = updater::retrieve_outputs(&mut **w,
+                                            None,
+                                            include_spent,
+                                            None,
+                                            &parent_key_id,
+                                            None,
+                                            None)?;
+    Ok(outputs)
+}
+
+// Transactions of the account. None means the active account.
+pub fn retrieve_account_txs<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    account: Option<String>,
+) -> Result<Vec<TxLogEntry>, Error>
+    where
+        L: WalletLCProvider<'a, C, K>,
+        C: NodeClient + 'a,
+        K: Keychain + 'a,
+{
+    wallet_lock!(wallet_inst, w);
+    let parent_key_id = account_parent_key(&mut **w, account)?;
+
+    let txs = updater::retrieve_txs(&mut **w, None, None, None, Some(&parent_key_id), false, None, None)?;
+    Ok(txs)
+}
+
+/// Not confirmed and not cancelled transaction
+pub struct PendingTx {
+    pub tx: TxLogEntry,
+    /// Stored transaction exists, so it can be reposted
+    pub has_stored_tx: bool,
+    /// Height of the kernel if the node already has it
+    pub kernel_height: Option<u64>,
+}
+
+// Pending send and receive transactions of the account, None means the active one. Kernels are checked with the node.
+pub fn retrieve_pending_txs<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    parent_key_id: Option<Identifier>,
+    refresh_from_node: bool,
+) -> Result<(bool, Vec<PendingTx>), Error>
+    where
+        L: WalletLCProvider<'a, C, K>,
+        C: NodeClient + 'a,
+        K: Keychain + 'a,
+{
+    let mut validated = false;
+    if refresh_from_node {
+        validated = sync(wallet_inst.clone(), true)?;
+    }
+
+    wallet_lock!(wallet_inst, w);
+    let parent_key_id = parent_key_id.unwrap_or(w.parent_key_id());
+
+    let txs = updater::retrieve_txs(&mut **w, None, None, None, Some(&parent_key_id), false, None, None)?;
+
+    let mut res: Vec<PendingTx> = Vec::new();
+    for tx in txs {
+        if tx.confirmed || tx.is_cancelled() {
+            continue;
+        }
+        if tx.tx_type != TxLogEntryType::TxSent && tx.tx_type != TxLogEntryType::TxReceived {
+            continue;
+        }
+
+        let stored_tx = tx.tx_slate_id
+            .and_then(|uuid| w.get_stored_tx_by_uuid(&uuid.to_string()).ok());
+        let excess = tx.kernel_excess.clone()
+            .or(stored_tx.as_ref().and_then(|t| t.body.kernels.first().map(|k| k.excess.clone())));
+
+        // Node errors are not fatal here, we just don't know where the kernel is
+        let kernel_height = match excess {
+            Some(excess) => w.w2n_client()
+                .get_kernel(&excess, tx.kernel_lookup_min_height, None)
+                .unwrap_or(None)
+                .map(|(_kernel, height, _mmr_index)| height),
+            None => None,
+        };
+
+        res.push(PendingTx {
+            has_stored_tx: stored_tx.is_some(),
+            kernel_height,
+            tx,
+        });
+    }
+
+    Ok((validated, res))
+}
+
+/// Background policies for the stuck transactions
+#[derive(Clone, Debug, Default)]
+pub struct TxPolicy {
+    /// Repost the stored transaction if its kernel is not on chain after this number of blocks
+    pub repost_blocks: Option<u64>,
+    /// Cancel the sent transaction when its TTL cutoff height is reached
+    pub cancel_expired: bool,
+    /// Cancel the sent transaction if its kernel is not on chain after this number of blocks
+    pub cancel_after_blocks: Option<u64>,
+}
+
+impl TxPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.repost_blocks.is_some() || self.cancel_expired || self.cancel_after_blocks.is_some()
+    }
+}
+
+// Repost or cancel the stuck transactions of every account according to the policy. Every action is logged.
+// reposted keeps the height of the last repost for the slate id, so the tx is reposted once every repost_blocks.
+pub fn apply_tx_policy<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    policy: &TxPolicy,
+    reposted: &mut HashMap<String, u64>,
+) -> Result<(), Error>
+    where
+        L: WalletLCProvider<'a, C, K>,
+        C: NodeClient + 'a,
+        K: Keychain + 'a,
+{
+    let (height, online) = node_height(wallet_inst.clone())?;
+    if !online {
+        return Ok(());
+    }
+
+    for account in accounts(wallet_inst.clone())? {
+        let (_validated, pending) = retrieve_pending_txs(wallet_inst.clone(), Some(account.path.clone()), false)?;
+
+        for p in pending {
+            let tx = &p.tx;
+            // Receives are left to the sender. Mined sends will be confirmed with the next update.
+            if tx.tx_type != TxLogEntryType::TxSent || p.kernel_height.is_some() {
+                continue;
+            }
+            let age = tx.kernel_lookup_min_height.map(|h| height.saturating_sub(h));
+
+            let expired = policy.cancel_expired && tx.ttl_cutoff_height.map(|ttl| height >= ttl).unwrap_or(false);
+            let too_old = match (policy.cancel_after_blocks, age) {
+                (Some(limit), Some(age)) => age >= limit,
+                _ => false,
+            };
+            if expired || too_old {
+                let res = {
+                    wallet_lock!(wallet_inst, w);
+                    grin_wallet_libwallet::internal::tx::cancel_tx(&mut **w, None, &account.path, Some(tx.id), None)
+                };
+                match res {
+                    Ok(_) => info!("Transaction {} of account {} is cancelled by the policy, {}", tx.id, account.label,
+                                   if expired { "TTL cutoff height is reached" } else { "it is not on chain for too long" }),
+                    Err(e) => warn!("Unable to cancel transaction {} of account {} by the policy, {}", tx.id, account.label, e),
+                }
+                if let Some(slate_id) = tx.tx_slate_id {
+                    reposted.remove(&slate_id.to_string());
+                }
+                continue;
+            }
+
+            if let (Some(repost_blocks), Some(age), Some(slate_id)) = (policy.repost_blocks, age, tx.tx_slate_id) {
+                let slate_id = slate_id.to_string();
+                let last_repost = reposted.get(&slate_id).cloned().unwrap_or(0);
+                if !p.has_stored_tx || age < repost_blocks || height < last_repost + repost_blocks {
+                    continue;
+                }
+                let res = get_stored_tx(wallet_inst.clone(), &slate_id)
+                    .and_then(|stored_tx| post_tx(wallet_inst.clone(), &stored_tx, false));
+                match res {
+                    Ok(_) => info!("Transaction {} of account {} is reposted by the policy, kernel is not on chain after {} blocks", tx.id, account.label, age),
+                    Err(e) => warn!("Unable to repost transaction {} of account {} by the policy, {}", tx.id, account.label, e),
+                }
+                reposted.insert(slate_id, height);
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn _retrieve_txs<'a, L, C, K>(
     wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
     refresh_from_node: bool,
@@ -696,6 +902,32 @@ pub fn retrieve_summary_info<'a, L, C, K>(
     Ok(res)
 }
 
+/// Summary info of every account. Refresh is done once for the whole wallet, the active account is not changed.
+pub fn retrieve_accounts_info<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    refresh_from_node: bool,
+    minimum_confirmations: u64,
+) -> Result<(bool, Vec<(AcctPathMapping, WalletInfo)>), Error>
+    where
+        L: WalletLCProvider<'a, C, K>,
+        C: NodeClient + 'a,
+        K: Keychain + 'a,
+{
+    let validated = if refresh_from_node {
+        sync(wallet_inst.clone(), true)?
+    } else {
+        false
+    };
+
+    wallet_lock!(wallet_inst, w);
+    let mut res = Vec::new();
+    for acct in keys::accounts(&mut **w)? {
+        let info = updater::retrieve_info(&mut **w, &acct.path, minimum_confirmations)?;
+        res.push((acct, info));
+    }
+    Ok((validated, res))
+}
+
 pub fn initiate_tx<'a, L, C, K>(
     wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
     active_account: Option<String>,
@@ -712,6 +944,7 @@ pub fn initiate_tx<'a, L, C, K>(
     status_send_channel: &Option<Sender<StatusMessage>>,
     ttl_blocks: u64,
     do_proof: bool,
+    estimate_only: bool, // If true, slate contains only fee and total amount locked, nothing is locked
 ) -> Result<Slate, Error>
     where
         L: WalletLCProvider<'a, C, K>,
@@ -790,7 +1023,7 @@ pub fn initiate_tx<'a, L, C, K>(
         /// 'true', the amount field in the slate will contain the total amount locked, not the provided
         /// transaction amount
         address: address_decorated,
-        estimate_only: None,
+        estimate_only: Some(estimate_only),
         /// Sender arguments. If present, the underlying function will also attempt to send the
         /// transaction to a destination and optionally finalize the result
         /// Whether or not to exclude change outputs, not needed in mwc713.
@@ -1276,6 +1509,50 @@ pub fn initiate_receive_tx<'a, L, C, K>(
     Ok(s)
 }
 
+// Add the payer inputs and change to the invoice slate. Caller is responsible for locking the outputs.
+pub fn process_invoice_tx<'a, L, C, K>(
+    wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    slate: &Slate,
+    active_account: Option<String>,
+    address: Option<String>,
+    minimum_confirmations: u64,
+    max_outputs: u32,
+    num_change_outputs: u32,
+    selection_strategy_is_use_all: bool,
+    outputs: Option<Vec<String>>,  // outputs to include into the transaction
+) -> Result<Slate, Error>
+    where
+        L: WalletLCProvider<'a, C, K>,
+        C: NodeClient + 'a,
+        K: Keychain + 'a,
+{
+    grin_wallet_libwallet::owner::update_wallet_state(wallet_inst.clone(), None, &None )?;
+
+    wallet_lock!(wallet_inst, w);
+
+    let params = grin_wallet_libwallet::InitTxArgs {
+        src_acct_name: active_account,
+        amount: slate.amount,
+        minimum_confirmations,
+        max_outputs,
+        num_change_outputs,
+        selection_strategy_is_use_all,
+        message: None,
+        target_slate_version: None,
+        ttl_blocks: None,
+        payment_proof_recipient_address: None,
+        address,
+        estimate_only: Some(false),
+        exclude_change_outputs: Some(false),
+        minimum_confirmations_change_outputs: 1,
+        send_args: None,
+        outputs,
+    };
+
+    let s = grin_wallet_libwallet::owner::process_invoice_tx(&mut **w, None, slate, params, false)?;
+    Ok(s)
+}
+
 pub fn receive_tx<'a, L, C, K>(
     wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
     address: Option<String>,
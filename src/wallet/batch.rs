@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::fs::{canonicalize, create_dir_all, File, rename};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use blake2_rfc::blake2b::blake2b;
+use chrono::Utc;
+use uuid::Uuid;
+use common::{json_store, Error, ErrorKind};
+
+const BATCH_DIR: &'static str = "send_batch";
+
+/// One payout from the batch CSV file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchPayment {
+    /// Address or @contact
+    pub address: String,
+    pub amount: u64,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum BatchRowStatus {
+    /// Outputs are locked, slate was not finalized. Row must not be sent again.
+    Locked,
+    /// Transaction finalized and posted
+    Sent,
+    /// Send failed before outputs was locked. Row will be retried.
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchRow {
+    pub payment: BatchPayment,
+    pub status: BatchRowStatus,
+    pub tx_slate_id: Option<Uuid>,
+    pub tx_id: Option<u32>,
+    pub error: Option<String>,
+}
+
+/// Progress of the batch payout. Stored in the wallet data dir, so the batch can be resumed after crash.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchJournal {
+    /// CSV file that was used for this batch
+    pub file: String,
+    /// Key: row number at the CSV file, starting from 1
+    pub rows: BTreeMap<usize, BatchRow>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl BatchJournal {
+    /// Load the journal for the CSV file, or create an empty one. The journal belongs to the full path
+    /// of the file, files with the same name from different directories have own journals.
+    pub fn open(data_dir: &str, csv_file: &str) -> Result<Self, Error> {
+        let dir = Path::new(data_dir).join(BATCH_DIR);
+        create_dir_all(&dir)?;
+        let canonical = canonicalize(csv_file)
+            .map_err(|e| ErrorKind::FileNotFound(csv_file.to_string(), format!("{}", e)))?;
+        let name = canonical
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or(csv_file.to_string());
+        let path_hash = blake2b(8, &[], canonical.to_string_lossy().as_bytes());
+        let path_hash: String = path_hash.as_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        let path = dir.join(format!("{}-{}.journal", name, path_hash));
+
+        let mut journal: BatchJournal = json_store::load(&path, "batch journal")?
+            .unwrap_or(BatchJournal {
+                file: canonical.to_string_lossy().to_string(),
+                rows: BTreeMap::new(),
+                path: PathBuf::new(),
+            });
+        journal.path = path;
+        Ok(journal)
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of the rows that are sent or locked
+    pub fn processed_rows(&self) -> usize {
+        self.rows.values().filter(|r| r.status != BatchRowStatus::Failed).count()
+    }
+
+    /// Keep the journal of the previous batch next to the new one and start from scratch
+    pub fn archive(&mut self) -> Result<(), Error> {
+        if self.path.exists() {
+            let archived = self.path.with_extension(format!("journal.{}", Utc::now().format("%Y%m%d%H%M%S")));
+            rename(&self.path, &archived)?;
+        }
+        self.rows.clear();
+        Ok(())
+    }
+
+    /// Rows that are sent or locked must not be sent again
+    pub fn is_done(&self, row: usize) -> bool {
+        match self.rows.get(&row) {
+            Some(r) => r.status != BatchRowStatus::Failed,
+            None => false,
+        }
+    }
+
+    /// Check that rows from the journal match the CSV file. Editing already processed rows is not allowed.
+    pub fn validate(&self, payments: &Vec<(usize, BatchPayment)>) -> Result<(), Error> {
+        for (row, r) in &self.rows {
+            if r.status == BatchRowStatus::Failed {
+                continue;
+            }
+            match payments.iter().find(|p| p.0 == *row) {
+                Some((_, p)) if *p == r.payment => (),
+                _ => return Err(ErrorKind::GenericError(format!(
+                    "Row {} of {} doesn't match the batch journal {}. Processed rows can't be changed.",
+                    row, self.file, self.path.display())).into()),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, row: usize, payment: &BatchPayment, status: BatchRowStatus, tx_slate_id: Option<Uuid>, tx_id: Option<u32>, error: Option<String>) -> Result<(), Error> {
+        self.rows.insert(row, BatchRow {
+            payment: payment.clone(),
+            status,
+            tx_slate_id,
+            tx_id,
+            error,
+        });
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        json_store::save(&self.path, self)
+    }
+}
+
+/// Read payments from the CSV file. Line format: address or @contact,amount[,message]
+/// Empty lines and lines started with '#' are skipped. Result is (row number, payment)
+pub fn read_batch_file(csv_file: &str) -> Result<Vec<(usize, BatchPayment)>, Error> {
+    let file = File::open(csv_file)
+        .map_err(|e| ErrorKind::FileNotFound(csv_file.to_string(), format!("{}", e)))?;
+
+    let mut res: Vec<(usize, BatchPayment)> = Vec::new();
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let row = idx + 1;
+        if line.trim().is_empty() || line.trim().starts_with("#") {
+            continue;
+        }
+        let fields = parse_csv_line(&line);
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(ErrorKind::GenericError(format!("Invalid line {} at {}, expected: address,amount[,message]", row, csv_file)).into());
+        }
+        let amount = grin_core::core::amount_from_hr_string(&fields[1])
+            .map_err(|_| ErrorKind::GenericError(format!("Invalid amount `{}` at line {} of {}", fields[1], row, csv_file)))?;
+        res.push((row, BatchPayment {
+            address: fields[0].clone(),
+            amount,
+            message: fields.get(2).filter(|m| !m.is_empty()).cloned(),
+        }));
+    }
+    Ok(res)
+}
+
+/// Split CSV line into the fields. Fields can be quoted, quote inside quoted field is escaped as "".
+pub fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches(|c| c == '\r' || c == '\n').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            },
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            },
+            ',' if !quoted => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            },
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields() {
+        assert_eq!(parse_csv_line("@alice, 1.5 ,thanks"), vec!["@alice", "1.5", "thanks"]);
+        assert_eq!(parse_csv_line("@alice,1.5\r\n"), vec!["@alice", "1.5"]);
+    }
+
+    #[test]
+    fn quoted_fields() {
+        assert_eq!(parse_csv_line(r#"@alice,2,"June payout, part 1""#), vec!["@alice", "2", "June payout, part 1"]);
+        assert_eq!(parse_csv_line(r#"@alice,2, "say ""hi""""#), vec!["@alice", "2", r#"say "hi""#]);
+    }
+
+    #[test]
+    fn empty_and_trailing_fields() {
+        assert_eq!(parse_csv_line("@alice,2,"), vec!["@alice", "2", ""]);
+        assert_eq!(parse_csv_line(r#"@alice,2,"""#), vec!["@alice", "2", ""]);
+        assert_eq!(parse_csv_line(""), vec![""]);
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use common::{json_store, Error};
+
+const COIN_CONTROL_FILE: &'static str = "coin_control.json";
+
+/// Frozen outputs and output labels. Outputs are identified by the commitment.
+/// Stored in the wallet data dir, mwc-wallet doesn't have a place for that data.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CoinControl {
+    /// Frozen outputs are excluded from any selection
+    pub frozen: BTreeSet<String>,
+    /// Key: commitment, value: label
+    pub labels: BTreeMap<String, String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl CoinControl {
+    /// Load coin control data from the wallet data dir. Empty data if nothing was stored yet.
+    pub fn open(data_dir: &str) -> Result<Self, Error> {
+        let path = Path::new(data_dir).join(COIN_CONTROL_FILE);
+        let mut coin_control: CoinControl = json_store::load(&path, "coin control data")?
+            .unwrap_or_default();
+        coin_control.path = path;
+        Ok(coin_control)
+    }
+
+    pub fn is_frozen(&self, commit: &str) -> bool {
+        self.frozen.contains(commit)
+    }
+
+    pub fn get_label(&self, commit: &str) -> Option<&String> {
+        self.labels.get(commit)
+    }
+
+    pub fn freeze(&mut self, commit: &str) -> Result<(), Error> {
+        self.frozen.insert(commit.to_string());
+        self.save()
+    }
+
+    pub fn unfreeze(&mut self, commit: &str) -> Result<(), Error> {
+        self.frozen.remove(commit);
+        self.save()
+    }
+
+    /// Set the output label, empty label removes it
+    pub fn set_label(&mut self, commit: &str, label: &str) -> Result<(), Error> {
+        if label.is_empty() {
+            self.labels.remove(commit);
+        } else {
+            self.labels.insert(commit.to_string(), label.to_string());
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        json_store::save(&self.path, self)
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use grin_core::core::amount_to_hr_string;
+use grin_wallet_impls::{Address, CloseReason, SubscriptionHandler};
+use grin_wallet_libwallet::{Slate, VersionedSlate};
+use grin_wallet_libwallet::proof::tx_proof::TxProof;
+
+use common::{json_store, Error, ErrorKind, Mutex};
+
+const INVOICE_QUEUE_FILE: &'static str = "invoice_queue.json";
+
+lazy_static! {
+    // The listener thread parks the invoices while the commands approve or reject them
+    static ref INVOICE_QUEUE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Open the queue, change and save it under the lock, so the concurrent changes are not lost
+pub fn with_queue<T, F>(data_dir: &str, f: F) -> Result<T, Error>
+    where F: FnOnce(&mut InvoiceQueue) -> Result<T, Error>
+{
+    let _guard = INVOICE_QUEUE_LOCK.lock();
+    let mut queue = InvoiceQueue::open(data_dir)?;
+    f(&mut queue)
+}
+
+/// Incoming invoice that waits for the manual approval
+#[derive(Serialize, Deserialize, Clone)]
+pub struct QueuedInvoice {
+    pub id: u32,
+    pub slate_id: Uuid,
+    /// Address of the issuer, the paid invoice is sent back there
+    pub from: String,
+    pub amount: u64,
+    pub message: Option<String>,
+    pub received: DateTime<Utc>,
+    /// Invoice slate as it was received
+    pub slate: VersionedSlate,
+}
+
+/// Invoices above max_auto_accept_invoice, stored in the wallet data dir until they are approved,
+/// rejected or expired
+#[derive(Serialize, Deserialize, Default)]
+pub struct InvoiceQueue {
+    next_id: u32,
+    pub invoices: BTreeMap<u32, QueuedInvoice>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl InvoiceQueue {
+    // Load the queue from the wallet data dir. Empty if nothing was stored yet.
+    fn open(data_dir: &str) -> Result<Self, Error> {
+        let path = Path::new(data_dir).join(INVOICE_QUEUE_FILE);
+        let mut queue: InvoiceQueue = json_store::load(&path, "invoice queue")?
+            .unwrap_or(InvoiceQueue {
+                next_id: 1,
+                ..InvoiceQueue::default()
+            });
+        queue.path = path;
+        Ok(queue)
+    }
+
+    pub fn add(&mut self, from: &str, slate: &VersionedSlate) -> Result<u32, Error> {
+        let s: Slate = slate.clone().into();
+        // The same invoice might be delivered again
+        if let Some(invoice) = self.invoices.values().find(|i| i.slate_id == s.id) {
+            return Ok(invoice.id);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.invoices.insert(id, QueuedInvoice {
+            id,
+            slate_id: s.id,
+            from: from.to_string(),
+            amount: s.amount,
+            message: s.participant_data.get(0).and_then(|p| p.message.clone()),
+            received: Utc::now(),
+            slate: slate.clone(),
+        });
+        self.save()?;
+        Ok(id)
+    }
+
+    pub fn get(&self, id: u32) -> Result<&QueuedInvoice, Error> {
+        self.invoices.get(&id)
+            .ok_or(ErrorKind::GenericError(format!("Invoice {} is not found in the queue", id)).into())
+    }
+
+    pub fn remove(&mut self, id: u32) -> Result<QueuedInvoice, Error> {
+        let invoice = self.invoices.remove(&id)
+            .ok_or(ErrorKind::GenericError(format!("Invoice {} is not found in the queue", id)))?;
+        self.save()?;
+        Ok(invoice)
+    }
+
+    /// Discard invoices received more than expiry_hours ago. Return the discarded invoices.
+    pub fn expire(&mut self, expiry_hours: Option<u64>) -> Result<Vec<QueuedInvoice>, Error> {
+        let expiry_hours = match expiry_hours {
+            Some(h) => h,
+            None => return Ok(Vec::new()),
+        };
+        let cutoff = Utc::now() - Duration::hours(expiry_hours as i64);
+        let expired: Vec<u32> = self.invoices.values()
+            .filter(|i| i.received < cutoff)
+            .map(|i| i.id)
+            .collect();
+        if expired.is_empty() {
+            return Ok(Vec::new());
+        }
+        let res = expired.iter().filter_map(|id| self.invoices.remove(id)).collect();
+        self.save()?;
+        Ok(res)
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        json_store::save(&self.path, self)
+    }
+}
+
+// Invoice slate has only the issuer data and no inputs. Send slate has the sender inputs.
+fn is_invoice(slate: &Slate) -> bool {
+    slate.participant_data.len() == 1 && slate.tx.body.inputs.is_empty()
+}
+
+/// mwcmqs handler that parks the invoices above max_auto_accept_invoice in the queue instead of
+/// passing them to the controller, which would reject them. Everything else goes to the controller.
+pub struct InvoiceQueueHandler<H: SubscriptionHandler> {
+    inner: H,
+    max_auto_accept_invoice: Option<u64>,
+    expiry_hours: Option<u64>,
+    data_dir: String,
+}
+
+impl<H: SubscriptionHandler> InvoiceQueueHandler<H> {
+    pub fn new(inner: H, max_auto_accept_invoice: Option<u64>, expiry_hours: Option<u64>, data_dir: String) -> Self {
+        InvoiceQueueHandler {
+            inner,
+            max_auto_accept_invoice,
+            expiry_hours,
+            data_dir,
+        }
+    }
+
+    fn park(&self, from: &dyn Address, slate: &VersionedSlate) -> Result<u32, Error> {
+        with_queue(&self.data_dir, |queue| {
+            queue.expire(self.expiry_hours)?;
+            queue.add(&from.to_string(), slate)
+        })
+    }
+}
+
+impl<H: SubscriptionHandler> SubscriptionHandler for InvoiceQueueHandler<H> {
+    fn on_open(&self) {
+        self.inner.on_open()
+    }
+
+    fn on_slate(&self, from: &dyn Address, slate: &VersionedSlate, proof: Option<&mut TxProof>) {
+        let s: Slate = slate.clone().into();
+        let auto_accept = self.max_auto_accept_invoice.map(|max| s.amount <= max).unwrap_or(false);
+        if !is_invoice(&s) || auto_accept {
+            return self.inner.on_slate(from, slate, proof);
+        }
+
+        match self.park(from, slate) {
+            Ok(id) => cli_message!(
+                "invoice [{}] for [{}] MWCs from [{}] is waiting for approval, see `invoices list`",
+                id,
+                amount_to_hr_string(s.amount, false),
+                from.get_stripped()
+            ),
+            Err(e) => cli_message!("ERROR: unable to store the invoice from [{}], {}", from.get_stripped(), e),
+        }
+    }
+
+    fn on_close(&self, result: CloseReason) {
+        self.inner.on_close(result)
+    }
+
+    fn on_dropped(&self) {
+        self.inner.on_dropped()
+    }
+
+    fn on_reestablished(&self) {
+        self.inner.on_reestablished()
+    }
+
+    fn set_notification_channels(&self, slate_id: &Uuid, slate_send_channel: Sender<Slate>) {
+        self.inner.set_notification_channels(slate_id, slate_send_channel)
+    }
+
+    fn reset_notification_channels(&self, slate_id: &Uuid) {
+        self.inner.reset_notification_channels(slate_id)
+    }
+}
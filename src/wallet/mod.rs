@@ -1,4 +1,16 @@
+pub mod account_archive;
 pub mod api;
+pub mod batch;
+pub mod coin_control;
+pub mod invoice_queue;
+pub mod payment_requests;
+pub mod selection;
+pub mod slate_armor;
+pub mod statement;
+pub mod stats;
+pub mod tx_export;
+pub mod tx_filter;
+pub mod tx_notes;
 pub mod wallet;
 
 pub use self::wallet::Wallet;
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use grin_core::core::amount_to_hr_string;
+use grin_wallet_libwallet::{TxLogEntry, TxLogEntryType};
+
+use common::{json_store, Error, ErrorKind};
+
+const PAYMENT_REQUESTS_FILE: &'static str = "payment_requests.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RequestStatus {
+    Open,
+    /// Some payments arrived, but less than the requested amount
+    Underpaid,
+    Paid,
+    Expired,
+}
+
+impl RequestStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RequestStatus::Open => "open",
+            RequestStatus::Underpaid => "underpaid",
+            RequestStatus::Paid => "paid",
+            RequestStatus::Expired => "expired",
+        }
+    }
+}
+
+/// Payment request of the merchant. The payer puts the tag into the transaction message, so the
+/// receives can be matched by the messages of the tx log.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaymentRequest {
+    pub id: u32,
+    pub tag: String,
+    pub amount: u64,
+    pub memo: Option<String>,
+    pub created: DateTime<Utc>,
+    pub expires: Option<DateTime<Utc>>,
+    pub status: RequestStatus,
+    /// Received amount of the matched transactions, nanoMWC
+    pub received: u64,
+    /// All matched transactions are confirmed
+    pub confirmed: bool,
+    /// Matched transactions as (account, tx id)
+    pub txs: Vec<(String, u32)>,
+}
+
+impl PaymentRequest {
+    /// Message the payer has to send together with the payment
+    pub fn payment_message(&self) -> String {
+        match &self.memo {
+            Some(memo) => format!("{} {}", self.tag, memo),
+            None => self.tag.clone(),
+        }
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.map(|e| e <= now).unwrap_or(false)
+    }
+
+    // Receives that carry the tag and were created while the request was open
+    fn matches(&self, tx: &TxLogEntry) -> bool {
+        if tx.tx_type != TxLogEntryType::TxReceived || tx.creation_ts < self.created {
+            return false;
+        }
+        if self.expires.map(|e| tx.creation_ts > e).unwrap_or(false) {
+            return false;
+        }
+        tx.messages.as_ref()
+            .map(|m| m.messages.iter().any(|m| m.message.as_ref().map(|s| has_tag(s, &self.tag)).unwrap_or(false)))
+            .unwrap_or(false)
+    }
+
+    /// Recompute the state from the transactions of all accounts. Paid requests are final.
+    fn update(&mut self, txs: &[(String, TxLogEntry)], now: DateTime<Utc>) {
+        if self.status == RequestStatus::Paid && self.confirmed {
+            return;
+        }
+
+        let matched: Vec<&(String, TxLogEntry)> = txs.iter().filter(|(_, tx)| self.matches(tx)).collect();
+        self.received = matched.iter().map(|(_, tx)| tx.amount_credited.saturating_sub(tx.amount_debited)).sum();
+        self.confirmed = !matched.is_empty() && matched.iter().all(|(_, tx)| tx.confirmed);
+        self.txs = matched.iter().map(|(account, tx)| (account.clone(), tx.id)).collect();
+        self.status = if self.received >= self.amount {
+            RequestStatus::Paid
+        } else if self.received > 0 {
+            RequestStatus::Underpaid
+        } else if self.is_expired(now) {
+            RequestStatus::Expired
+        } else {
+            RequestStatus::Open
+        };
+    }
+}
+
+// Tag must be a separate word, so `req-1` doesn't match `req-12`
+fn has_tag(message: &str, tag: &str) -> bool {
+    message.split(|c: char| c.is_whitespace() || c == ',' || c == ';' || c == ':')
+        .any(|w| w == tag)
+}
+
+/// Parse the request lifetime like 30m, 2h or 7d
+pub fn parse_expiry(s: &str) -> Result<Duration, Error> {
+    let err = || ErrorKind::GenericError(format!("invalid expiry `{}`, use minutes, hours or days like 30m, 2h or 7d", s));
+    let s = s.trim();
+    let (num, unit_secs) = if s.ends_with('m') {
+        (&s[..s.len() - 1], 60)
+    } else if s.ends_with('h') {
+        (&s[..s.len() - 1], 3600)
+    } else if s.ends_with('d') {
+        (&s[..s.len() - 1], 86400)
+    } else {
+        return Err(err().into());
+    };
+    let num: i64 = num.parse().map_err(|_| err())?;
+    if num <= 0 {
+        return Err(err().into());
+    }
+    // Duration panics out of its range, and the expiry time is computed from it later
+    let secs = num.checked_mul(unit_secs)
+        .filter(|secs| *secs <= i64::max_value() / 1000)
+        .ok_or(err())?;
+    let expiry = Duration::seconds(secs);
+    if Utc::now().checked_add_signed(expiry).is_none() {
+        return Err(err().into());
+    }
+    Ok(expiry)
+}
+
+/// Payment requests of the wallet, stored in the wallet data dir
+#[derive(Serialize, Deserialize, Default)]
+pub struct PaymentRequests {
+    next_id: u32,
+    pub requests: BTreeMap<u32, PaymentRequest>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl PaymentRequests {
+    /// Load the requests from the wallet data dir. Empty if nothing was stored yet.
+    pub fn open(data_dir: &str) -> Result<Self, Error> {
+        let path = Path::new(data_dir).join(PAYMENT_REQUESTS_FILE);
+        let mut requests: PaymentRequests = json_store::load(&path, "payment requests")?
+            .unwrap_or(PaymentRequests {
+                next_id: 1,
+                ..PaymentRequests::default()
+            });
+        requests.path = path;
+        Ok(requests)
+    }
+
+    pub fn create(&mut self, amount: u64, memo: Option<String>, expires: Option<Duration>) -> Result<PaymentRequest, Error> {
+        if amount == 0 {
+            return Err(ErrorKind::GenericError("payment request amount must be positive".to_string()).into());
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        // Random part keeps the tags unique between the wallets of the same merchant
+        let tag = format!("req-{}-{}", id, &Uuid::new_v4().to_simple().to_string()[..8]);
+        let now = Utc::now();
+        let expires = match expires {
+            Some(d) => Some(now.checked_add_signed(d)
+                .ok_or(ErrorKind::GenericError("payment request expiry is too far in the future".to_string()))?),
+            None => None,
+        };
+        let request = PaymentRequest {
+            id,
+            tag,
+            amount,
+            memo,
+            created: now,
+            expires,
+            status: RequestStatus::Open,
+            received: 0,
+            confirmed: false,
+            txs: Vec::new(),
+        };
+        self.requests.insert(id, request.clone());
+        self.save()?;
+        Ok(request)
+    }
+
+    pub fn get(&self, id: u32) -> Result<&PaymentRequest, Error> {
+        self.requests.get(&id)
+            .ok_or(ErrorKind::GenericError(format!("Payment request {} is not found", id)).into())
+    }
+
+    /// Match the transactions, (account, tx) pairs, against the requests and store the new state
+    pub fn update(&mut self, txs: &[(String, TxLogEntry)]) -> Result<(), Error> {
+        let now = Utc::now();
+        for request in self.requests.values_mut() {
+            request.update(txs, now);
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        json_store::save(&self.path, self)
+    }
+}
+
+fn status_text(request: &PaymentRequest) -> String {
+    match request.status {
+        RequestStatus::Paid | RequestStatus::Underpaid if !request.confirmed =>
+            format!("{}, unconfirmed", request.status.name()),
+        _ => request.status.name().to_string(),
+    }
+}
+
+pub fn print_table(requests: &Vec<PaymentRequest>) {
+    println!("{:>5}  {:<19}  {:<19}  {:>14}  {:>14}  {:<22}  {}",
+             "Id", "Created", "Expires", "Amount", "Received", "Status", "Memo");
+    for r in requests {
+        println!("{:>5}  {:<19}  {:<19}  {:>14}  {:>14}  {:<22}  {}",
+                 r.id,
+                 r.created.format("%Y-%m-%d %H:%M:%S"),
+                 r.expires.map(|e| e.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or("never".to_string()),
+                 amount_to_hr_string(r.amount, true),
+                 amount_to_hr_string(r.received, true),
+                 status_text(r),
+                 r.memo.clone().unwrap_or(String::new()));
+    }
+}
+
+/// Print the request with the instructions for the payer
+pub fn print_details(request: &PaymentRequest, address: &str) {
+    println!("Payment request {}", request.id);
+    println!("    Status:    {}", status_text(request));
+    println!("    Amount:    {} MWC", amount_to_hr_string(request.amount, false));
+    println!("    Received:  {} MWC", amount_to_hr_string(request.received, false));
+    if let Some(memo) = &request.memo {
+        println!("    Memo:      {}", memo);
+    }
+    println!("    Created:   {}", request.created.format("%Y-%m-%d %H:%M:%S"));
+    match request.expires {
+        Some(e) => println!("    Expires:   {}", e.format("%Y-%m-%d %H:%M:%S")),
+        None => println!("    Expires:   never"),
+    }
+    for (account, id) in &request.txs {
+        println!("    Payment:   transaction {} in account {}", id, account);
+    }
+    if request.status == RequestStatus::Open || request.status == RequestStatus::Underpaid {
+        println!("\nPay {} MWC to {} with the message \"{}\"",
+                 amount_to_hr_string(request.amount - request.received, false),
+                 address,
+                 request.payment_message());
+        println!("For example: send {} --to {} -m \"{}\"",
+                 amount_to_hr_string(request.amount - request.received, false),
+                 address,
+                 request.payment_message());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry_suffixes() {
+        assert_eq!(parse_expiry("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_expiry(" 2h ").unwrap(), Duration::hours(2));
+        assert_eq!(parse_expiry("7d").unwrap(), Duration::days(7));
+    }
+
+    #[test]
+    fn invalid_expiry() {
+        for s in &["", "m", "30", "30s", "-1h", "0d", "1.5h", "h1", "1hh"] {
+            assert!(parse_expiry(s).is_err(), "`{}` must be rejected", s);
+        }
+        // Multibyte suffix must not panic on the slicing
+        assert!(parse_expiry("1日").is_err());
+        assert!(parse_expiry("1é").is_err());
+    }
+
+    #[test]
+    fn expiry_overflow() {
+        assert!(parse_expiry(&format!("{}d", i64::max_value())).is_err());
+        assert!(parse_expiry(&format!("{}m", i64::max_value() / 60 + 1)).is_err());
+        assert!(parse_expiry("99999999999999999999d").is_err());
+        // Fits the Duration, but not the date
+        assert!(parse_expiry("100000000000d").is_err());
+    }
+
+    #[test]
+    fn tag_is_a_separate_word() {
+        assert!(has_tag("req-1-abc", "req-1-abc"));
+        assert!(has_tag("req-1-abc order 5", "req-1-abc"));
+        assert!(has_tag("order 5,req-1-abc;thanks", "req-1-abc"));
+        assert!(has_tag("paid:req-1-abc", "req-1-abc"));
+        assert!(!has_tag("req-1-abcd", "req-1-abc"));
+        assert!(!has_tag("xreq-1-abc", "req-1-abc"));
+        assert!(!has_tag("", "req-1-abc"));
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::BTreeMap;
+
+use grin_core::libtx::tx_fee;
+use grin_wallet_libwallet::OutputData;
+
+/// Input selection strategies implemented by mwc713. 'smallest', 'all' and 'custom' are handled by libwallet.
+/// A strategy picks the inputs, the result is passed to libwallet as the list of outputs to spend.
+pub const STRATEGIES: &'static [&'static str] = &["largest-first", "oldest-first", "exact-match", "privacy"];
+
+/// Output that can be spent, with the address of the transaction that created it if known
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub output: OutputData,
+    pub counterparty: Option<String>,
+}
+
+/// What the selected inputs have to pay for
+#[derive(Debug, Clone)]
+pub struct SelectionParams {
+    pub amount: u64,
+    pub change_outputs: usize,
+    /// Number of the receiver outputs
+    pub routputs: usize,
+    pub max_outputs: usize,
+}
+
+impl SelectionParams {
+    /// Fee for the number of inputs. libwallet first tries to build the transaction without change,
+    /// change outputs are paid only if the inputs are more than amount plus fee.
+    pub fn fee(&self, inputs: usize, with_change: bool) -> u64 {
+        let outputs = if with_change {
+            self.routputs + self.change_outputs
+        } else {
+            self.routputs
+        };
+        tx_fee(inputs, outputs, 1, None)
+    }
+
+    /// Inputs pay the amount and the fee exactly, no change output is needed
+    pub fn is_exact(&self, inputs: &[&Candidate]) -> bool {
+        !inputs.is_empty() && total(inputs) == self.amount + self.fee(inputs.len(), false)
+    }
+
+    pub fn is_enough(&self, inputs: &[&Candidate]) -> bool {
+        self.is_exact(inputs) || total(inputs) >= self.amount + self.fee(inputs.len(), true)
+    }
+}
+
+pub trait SelectionStrategy {
+    /// Select the inputs from the candidates. None if the strategy can't find a suitable set.
+    fn select<'a>(&self, candidates: &'a [Candidate], params: &SelectionParams) -> Option<Vec<&'a Candidate>>;
+
+    /// Inputs for sending everything, like `send ALL`. Every candidate by default.
+    fn select_all<'a>(&self, candidates: &'a [Candidate]) -> Vec<&'a Candidate> {
+        candidates.iter().collect()
+    }
+}
+
+pub fn get_strategy(name: &str) -> Option<Box<dyn SelectionStrategy>> {
+    match name {
+        "largest-first" => Some(Box::new(LargestFirst)),
+        "oldest-first" => Some(Box::new(OldestFirst)),
+        "exact-match" => Some(Box::new(ExactMatch)),
+        "privacy" => Some(Box::new(Privacy)),
+        _ => None,
+    }
+}
+
+fn total(inputs: &[&Candidate]) -> u64 {
+    inputs.iter().map(|c| c.output.value).sum()
+}
+
+// Take the candidates in the given order until they cover the amount and the fee
+fn take_until_enough<'a>(ordered: Vec<&'a Candidate>, params: &SelectionParams) -> Option<Vec<&'a Candidate>> {
+    let mut res: Vec<&Candidate> = Vec::new();
+    for c in ordered {
+        if res.len() >= params.max_outputs {
+            break;
+        }
+        res.push(c);
+        if params.is_enough(&res) {
+            return Some(res);
+        }
+    }
+    None
+}
+
+/// Biggest outputs first, minimizes the number of inputs and the fee
+pub struct LargestFirst;
+
+impl SelectionStrategy for LargestFirst {
+    fn select<'a>(&self, candidates: &'a [Candidate], params: &SelectionParams) -> Option<Vec<&'a Candidate>> {
+        let mut ordered: Vec<&Candidate> = candidates.iter().collect();
+        ordered.sort_by(|a, b| b.output.value.cmp(&a.output.value));
+        take_until_enough(ordered, params)
+    }
+}
+
+/// Outputs with the lowest height first
+pub struct OldestFirst;
+
+impl SelectionStrategy for OldestFirst {
+    fn select<'a>(&self, candidates: &'a [Candidate], params: &SelectionParams) -> Option<Vec<&'a Candidate>> {
+        let mut ordered: Vec<&Candidate> = candidates.iter().collect();
+        ordered.sort_by_key(|c| (c.output.height, c.output.value));
+        take_until_enough(ordered, params)
+    }
+}
+
+// Limit for the exact match search, wallets with many outputs would take forever otherwise
+const EXACT_MATCH_MAX_STEPS: usize = 100_000;
+
+/// Search for inputs that pay the amount and the fee exactly, so no change output is created.
+/// The search is limited, a match can be missed for the wallets with many outputs.
+pub struct ExactMatch;
+
+impl ExactMatch {
+    fn search<'a>(
+        ordered: &[&'a Candidate],
+        suffix_totals: &[u64],
+        start: usize,
+        selected: &mut Vec<&'a Candidate>,
+        params: &SelectionParams,
+        steps: &mut usize,
+    ) -> bool {
+        if params.is_exact(selected) {
+            return true;
+        }
+        if selected.len() >= params.max_outputs {
+            return false;
+        }
+
+        let selected_total = total(selected);
+        // Fee only goes down with more inputs, so the target can't be less than that
+        let min_target = params.amount + params.fee(params.max_outputs, false);
+
+        for i in start..ordered.len() {
+            *steps += 1;
+            if *steps > EXACT_MATCH_MAX_STEPS || selected_total + suffix_totals[i] < min_target {
+                return false;
+            }
+            let value = ordered[i].output.value;
+            if selected_total + value > params.amount + params.fee(selected.len() + 1, false) {
+                continue;
+            }
+            selected.push(ordered[i]);
+            if Self::search(ordered, suffix_totals, i + 1, selected, params, steps) {
+                return true;
+            }
+            selected.pop();
+        }
+        false
+    }
+}
+
+impl SelectionStrategy for ExactMatch {
+    fn select<'a>(&self, candidates: &'a [Candidate], params: &SelectionParams) -> Option<Vec<&'a Candidate>> {
+        let mut ordered: Vec<&Candidate> = candidates.iter().collect();
+        ordered.sort_by(|a, b| b.output.value.cmp(&a.output.value));
+
+        // suffix_totals[i] is the sum of ordered[i..]
+        let mut suffix_totals = vec![0u64; ordered.len() + 1];
+        for i in (0..ordered.len()).rev() {
+            suffix_totals[i] = suffix_totals[i + 1] + ordered[i].output.value;
+        }
+
+        let mut selected: Vec<&Candidate> = Vec::new();
+        let mut steps = 0;
+        match Self::search(&ordered, &suffix_totals, 0, &mut selected, params, &mut steps) {
+            true => Some(selected),
+            false => None,
+        }
+    }
+}
+
+/// Don't mix outputs received from different counterparties in one transaction. Outputs with unknown
+/// counterparty are grouped by the transaction that created them. The group that needs the fewest inputs wins.
+pub struct Privacy;
+
+impl Privacy {
+    fn groups<'a>(candidates: &'a [Candidate]) -> BTreeMap<String, Vec<&'a Candidate>> {
+        let mut groups: BTreeMap<String, Vec<&Candidate>> = BTreeMap::new();
+        for c in candidates {
+            let key = match (&c.counterparty, c.output.tx_log_entry) {
+                (Some(address), _) => address.clone(),
+                (None, Some(tx_id)) => format!("tx:{}", tx_id),
+                (None, None) => format!("output:{}", c.output.commit.clone().unwrap_or(String::new())),
+            };
+            groups.entry(key).or_insert(Vec::new()).push(c);
+        }
+        groups
+    }
+}
+
+impl SelectionStrategy for Privacy {
+    fn select<'a>(&self, candidates: &'a [Candidate], params: &SelectionParams) -> Option<Vec<&'a Candidate>> {
+        Privacy::groups(candidates)
+            .into_iter()
+            .filter_map(|(_, mut group)| {
+                group.sort_by_key(|c| c.output.value);
+                take_until_enough(group, params)
+            })
+            .min_by_key(|inputs| (inputs.len(), total(inputs)))
+    }
+
+    /// Everything of one counterparty, the group with the biggest total
+    fn select_all<'a>(&self, candidates: &'a [Candidate]) -> Vec<&'a Candidate> {
+        Privacy::groups(candidates)
+            .into_iter()
+            .map(|(_, group)| group)
+            .max_by_key(|group| total(group))
+            .unwrap_or(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grin_keychain::Identifier;
+    use grin_wallet_libwallet::OutputStatus;
+
+    const MWC: u64 = 1_000_000_000;
+
+    fn candidate(value: u64, height: u64, counterparty: Option<&str>, tx_log_entry: Option<u32>) -> Candidate {
+        Candidate {
+            output: OutputData {
+                root_key_id: Identifier::zero(),
+                key_id: Identifier::zero(),
+                n_child: 0,
+                commit: Some(format!("{:066x}", value ^ height)),
+                mmr_index: None,
+                value,
+                status: OutputStatus::Unspent,
+                height,
+                lock_height: 0,
+                is_coinbase: false,
+                tx_log_entry,
+            },
+            counterparty: counterparty.map(|c| c.to_string()),
+        }
+    }
+
+    fn plain(values: &[u64]) -> Vec<Candidate> {
+        values.iter().enumerate().map(|(i, v)| candidate(*v, i as u64 + 1, None, Some(i as u32))).collect()
+    }
+
+    fn params(amount: u64, max_outputs: usize) -> SelectionParams {
+        SelectionParams {
+            amount,
+            change_outputs: 1,
+            routputs: 1,
+            max_outputs,
+        }
+    }
+
+    fn values(selected: &Option<Vec<&Candidate>>) -> Option<Vec<u64>> {
+        selected.as_ref().map(|s| s.iter().map(|c| c.output.value).collect())
+    }
+
+    #[test]
+    fn largest_first() {
+        let candidates = plain(&[MWC, 5 * MWC, 10 * MWC, 3 * MWC]);
+        let selected = LargestFirst.select(&candidates, &params(12 * MWC, 500));
+        assert_eq!(values(&selected), Some(vec![10 * MWC, 5 * MWC]));
+
+        assert!(LargestFirst.select(&candidates, &params(19 * MWC, 500)).is_none());
+        // 10 + 5 is needed, but only one input is allowed
+        assert!(LargestFirst.select(&candidates, &params(12 * MWC, 1)).is_none());
+    }
+
+    #[test]
+    fn oldest_first() {
+        let candidates = vec![
+            candidate(10 * MWC, 30, None, Some(1)),
+            candidate(2 * MWC, 10, None, Some(2)),
+            candidate(3 * MWC, 20, None, Some(3)),
+        ];
+        let selected = OldestFirst.select(&candidates, &params(4 * MWC, 500));
+        assert_eq!(values(&selected), Some(vec![2 * MWC, 3 * MWC]));
+
+        let selected = OldestFirst.select(&candidates, &params(6 * MWC, 500));
+        assert_eq!(values(&selected), Some(vec![2 * MWC, 3 * MWC, 10 * MWC]));
+    }
+
+    #[test]
+    fn exact_match_hit() {
+        let p = params(6 * MWC - params(0, 500).fee(2, false), 500);
+        let candidates = plain(&[10 * MWC, 5 * MWC, 3 * MWC, MWC]);
+        let selected = ExactMatch.select(&candidates, &p);
+        assert_eq!(values(&selected), Some(vec![5 * MWC, MWC]));
+        assert!(p.is_exact(&selected.unwrap()));
+    }
+
+    #[test]
+    fn exact_match_miss() {
+        let p = params(7 * MWC, 500);
+        let candidates = plain(&[10 * MWC, 5 * MWC, MWC]);
+        assert!(ExactMatch.select(&candidates, &p).is_none());
+    }
+
+    #[test]
+    fn exact_match_max_outputs() {
+        let p = params(6 * MWC - params(0, 500).fee(2, false), 500);
+        let candidates = plain(&[5 * MWC, MWC]);
+        assert!(ExactMatch.select(&candidates, &p).is_some());
+
+        let p = SelectionParams { max_outputs: 1, ..p };
+        assert!(ExactMatch.select(&candidates, &p).is_none());
+    }
+
+    #[test]
+    fn exact_match_step_limit() {
+        // Equal outputs can't pay a half MWC exactly, every combination is tried until the limit
+        let candidates = plain(&vec![MWC; 40]);
+        let p = params(10 * MWC + MWC / 2, 500);
+
+        let mut ordered: Vec<&Candidate> = candidates.iter().collect();
+        ordered.sort_by(|a, b| b.output.value.cmp(&a.output.value));
+        let mut suffix_totals = vec![0u64; ordered.len() + 1];
+        for i in (0..ordered.len()).rev() {
+            suffix_totals[i] = suffix_totals[i + 1] + ordered[i].output.value;
+        }
+
+        let mut selected = Vec::new();
+        let mut steps = 0;
+        assert!(!ExactMatch::search(&ordered, &suffix_totals, 0, &mut selected, &p, &mut steps));
+        assert!(steps > EXACT_MATCH_MAX_STEPS);
+        assert!(ExactMatch.select(&candidates, &p).is_none());
+    }
+
+    #[test]
+    fn privacy_doesnt_mix_counterparties() {
+        let candidates = vec![
+            candidate(3 * MWC, 1, Some("alice"), Some(1)),
+            candidate(3 * MWC, 2, Some("alice"), Some(2)),
+            candidate(7 * MWC, 3, Some("bob"), Some(3)),
+            candidate(4 * MWC, 4, None, Some(4)),
+            candidate(4 * MWC, 5, None, Some(5)),
+        ];
+
+        // Bob's output alone is enough, it needs fewer inputs than alice's
+        let selected = Privacy.select(&candidates, &params(5 * MWC, 500)).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].counterparty, Some("bob".to_string()));
+
+        let selected = Privacy.select(&candidates, &params(7 * MWC, 500));
+        assert!(selected.is_none(), "no single counterparty has 7 MWC plus the fee");
+
+        // Outputs without the counterparty are grouped by their transaction
+        let selected = Privacy.select(&candidates[3..], &params(5 * MWC, 500));
+        assert!(selected.is_none());
+    }
+
+    #[test]
+    fn select_all() {
+        let candidates = vec![
+            candidate(3 * MWC, 1, Some("alice"), Some(1)),
+            candidate(3 * MWC, 2, Some("alice"), Some(2)),
+            candidate(5 * MWC, 3, Some("bob"), Some(3)),
+        ];
+        assert_eq!(LargestFirst.select_all(&candidates).len(), 3);
+
+        // Alice's outputs together are more than bob's one
+        let selected = Privacy.select_all(&candidates);
+        assert_eq!(selected.len(), 2);
+        assert!(selected.iter().all(|c| c.counterparty == Some("alice".to_string())));
+
+        assert!(Privacy.select_all(&[]).is_empty());
+    }
+
+    #[test]
+    fn fee_boundary_without_change() {
+        let p = params(0, 500);
+        let amount = 5 * MWC - p.fee(1, false);
+        let p = params(amount, 500);
+        let candidates = plain(&[5 * MWC]);
+
+        // Pays the amount and the fee without the change output
+        let selected = LargestFirst.select(&candidates, &p).unwrap();
+        assert!(p.is_exact(&selected));
+        assert!(p.is_enough(&selected));
+
+        // One nanoMWC less to send leaves a change, which the input can't pay the fee for
+        let p = params(amount - 1, 500);
+        assert!(p.fee(1, true) > p.fee(1, false) + 1);
+        assert!(!p.is_enough(&candidates.iter().collect::<Vec<&Candidate>>()));
+        assert!(LargestFirst.select(&candidates, &p).is_none());
+    }
+}
@@ -0,0 +1,132 @@
+use grin_keychain::base58;
+
+use common::{Error, ErrorKind};
+
+const BEGIN_SLATE: &'static str = "-----BEGIN MWC SLATE-----";
+const END_SLATE: &'static str = "-----END MWC SLATE-----";
+const BEGIN_ENCRYPTED_SLATE: &'static str = "-----BEGIN MWC ENCRYPTED SLATE-----";
+const END_ENCRYPTED_SLATE: &'static str = "-----END MWC ENCRYPTED SLATE-----";
+
+// Length of the base58 lines, short enough for the mail clients
+const LINE_LENGTH: usize = 64;
+
+/// Payload of the armored slate
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArmoredSlate {
+    /// Slate json
+    Plain(String),
+    /// Slate encrypted to the mwcmqs address, the same query string as `encryptslate` prints
+    Encrypted(String),
+}
+
+/// Text that has the armored slate markers
+pub fn is_armored(text: &str) -> bool {
+    text.contains(BEGIN_SLATE) || text.contains(BEGIN_ENCRYPTED_SLATE)
+}
+
+/// Base58 with the checksum between the BEGIN/END markers
+pub fn armor(slate: &ArmoredSlate) -> String {
+    let (begin, end, payload) = match slate {
+        ArmoredSlate::Plain(json) => (BEGIN_SLATE, END_SLATE, json),
+        ArmoredSlate::Encrypted(query) => (BEGIN_ENCRYPTED_SLATE, END_ENCRYPTED_SLATE, query),
+    };
+    let encoded = base58::check_encode_slice(payload.as_bytes());
+
+    let mut res = format!("{}\n\n", begin);
+    for line in encoded.as_bytes().chunks(LINE_LENGTH) {
+        // base58 is ascii
+        res += &String::from_utf8_lossy(line);
+        res += "\n";
+    }
+    res += end;
+    res += "\n";
+    res
+}
+
+/// Extract the payload. Text around the markers, like the rest of the message, is ignored.
+pub fn dearmor(text: &str) -> Result<ArmoredSlate, Error> {
+    let (begin, end, encrypted) = if text.contains(BEGIN_ENCRYPTED_SLATE) {
+        (BEGIN_ENCRYPTED_SLATE, END_ENCRYPTED_SLATE, true)
+    } else {
+        (BEGIN_SLATE, END_SLATE, false)
+    };
+
+    let start = text.find(begin)
+        .ok_or(ErrorKind::GenericError("armored slate BEGIN marker is not found".to_string()))?
+        + begin.len();
+    let len = text[start..].find(end)
+        .ok_or(ErrorKind::GenericError("armored slate END marker is not found".to_string()))?;
+
+    // Mail and chat clients might reflow the lines or indent them
+    let encoded: String = text[start..start + len].chars().filter(|c| !c.is_whitespace()).collect();
+    let data = base58::from_check(&encoded)
+        .map_err(|e| ErrorKind::GenericError(format!("armored slate is damaged, {:?}", e)))?;
+    let payload = String::from_utf8(data)
+        .map_err(|_| ErrorKind::GenericError("armored slate is not a text".to_string()))?;
+
+    match encrypted {
+        true => Ok(ArmoredSlate::Encrypted(payload)),
+        false => Ok(ArmoredSlate::Plain(payload)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLATE_JSON: &'static str = r#"{"version_info":{"version":3},"id":"0436430c-2b02-624c-2032-570501212b00","amount":"1000000000"}"#;
+
+    #[test]
+    fn round_trip_plain() {
+        let slate = ArmoredSlate::Plain(SLATE_JSON.to_string());
+        let text = armor(&slate);
+        assert!(is_armored(&text));
+        assert!(text.starts_with(BEGIN_SLATE));
+        assert!(text.lines().all(|l| l.len() <= LINE_LENGTH || l == BEGIN_SLATE || l == END_SLATE));
+        assert_eq!(dearmor(&text).unwrap(), slate);
+    }
+
+    #[test]
+    fn round_trip_encrypted() {
+        let slate = ArmoredSlate::Encrypted("nonce=abc&msg=def".to_string());
+        let text = armor(&slate);
+        assert!(text.starts_with(BEGIN_ENCRYPTED_SLATE));
+        assert_eq!(dearmor(&text).unwrap(), slate);
+    }
+
+    #[test]
+    fn reflowed_and_indented_lines() {
+        let slate = ArmoredSlate::Plain(SLATE_JSON.to_string());
+        let text = armor(&slate);
+        let encoded: String = text.lines()
+            .filter(|l| !l.starts_with("-----"))
+            .collect();
+
+        // Quoted reply with the short lines and the text around the markers
+        let mut quoted = format!("Hi, here is the slate\n> {}\n", BEGIN_SLATE);
+        for line in encoded.as_bytes().chunks(20) {
+            quoted += &format!(">    {}\r\n", String::from_utf8_lossy(line));
+        }
+        quoted += &format!(">  {}\nThanks", END_SLATE);
+
+        assert_eq!(dearmor(&quoted).unwrap(), slate);
+    }
+
+    #[test]
+    fn checksum_failure() {
+        let text = armor(&ArmoredSlate::Plain(SLATE_JSON.to_string()));
+        let pos = BEGIN_SLATE.len() + 2;
+        let c = text.as_bytes()[pos] as char;
+        let replacement = if c == 'z' { "y" } else { "z" };
+        let damaged = format!("{}{}{}", &text[..pos], replacement, &text[pos + 1..]);
+        assert!(dearmor(&damaged).is_err());
+    }
+
+    #[test]
+    fn missing_markers() {
+        assert!(dearmor("no slate here").is_err());
+        let text = armor(&ArmoredSlate::Plain(SLATE_JSON.to_string()));
+        let truncated = text.replace(END_SLATE, "");
+        assert!(dearmor(&truncated).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use grin_core::core::amount_to_hr_string;
+use grin_wallet_libwallet::{TxLogEntry, TxLogEntryType};
+
+use common::{Error, ErrorKind};
+use super::tx_export::csv_field;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interval {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Interval {
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "day" => Ok(Interval::Day),
+            "week" => Ok(Interval::Week),
+            "month" => Ok(Interval::Month),
+            "year" => Ok(Interval::Year),
+            _ => Err(ErrorKind::GenericError(format!("invalid interval `{}`, use day, week, month or year", s)).into()),
+        }
+    }
+
+    /// Start of the n-th interval from the date. Months and years keep the day of the date, clamped to
+    /// the month length, so Jan 31 is followed by Feb 28 and Mar 31.
+    pub fn nth(&self, date: NaiveDate, n: u32) -> NaiveDate {
+        match self {
+            Interval::Day => date + Duration::days(n as i64),
+            Interval::Week => date + Duration::weeks(n as i64),
+            Interval::Month => add_months(date, n),
+            Interval::Year => add_months(date, 12 * n),
+        }
+    }
+}
+
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+    let total = date.year() * 12 + date.month0() as i32 + months as i32;
+    let (year, month) = (total / 12, total as u32 % 12 + 1);
+    let mut day = date.day();
+    loop {
+        if let Some(d) = NaiveDate::from_ymd_opt(year, month, day) {
+            return d;
+        }
+        day -= 1;
+    }
+}
+
+/// Balance movement of the account during one interval. Amounts are in nanoMWC.
+#[derive(Serialize, Debug, Clone)]
+pub struct StatementPeriod {
+    pub start: String,
+    /// Last day of the interval
+    pub end: String,
+    pub opening_balance: i64,
+    pub received: u64,
+    /// Sent amount without fees
+    pub sent: u64,
+    pub fees: u64,
+    pub closing_balance: i64,
+    /// Part of the closing balance that was confirmed at the end of the interval
+    pub confirmed: i64,
+    pub pending: i64,
+}
+
+fn net_amount(tx: &TxLogEntry) -> i64 {
+    tx.amount_credited as i64 - tx.amount_debited as i64
+}
+
+fn is_cancelled(tx: &TxLogEntry) -> bool {
+    tx.tx_type == TxLogEntryType::TxSentCancelled || tx.tx_type == TxLogEntryType::TxReceivedCancelled
+}
+
+// Restored transactions might not have the confirmation time, creation time is the best guess
+fn confirmation_time(tx: &TxLogEntry) -> Option<DateTime<Utc>> {
+    match tx.confirmed {
+        true => Some(tx.confirmation_ts.unwrap_or(tx.creation_ts)),
+        false => None,
+    }
+}
+
+/// Balance of the not cancelled transactions created before the time, and the confirmed part of it
+pub fn balance_at(txs: &[TxLogEntry], time: DateTime<Utc>) -> (i64, i64) {
+    txs.iter()
+        .filter(|tx| !is_cancelled(tx) && tx.creation_ts < time)
+        .fold((0, 0), |(total, confirmed), tx| {
+            let is_confirmed = confirmation_time(tx).map(|t| t < time).unwrap_or(false);
+            (total + net_amount(tx), confirmed + if is_confirmed { net_amount(tx) } else { 0 })
+        })
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc)
+}
+
+/// Split the time from `from` to `to` (both inclusive dates) into the intervals and compute the balances
+/// from the account transactions
+pub fn build_statement(txs: &[TxLogEntry], from: NaiveDate, to: NaiveDate, interval: Interval) -> Result<Vec<StatementPeriod>, Error> {
+    if to < from {
+        return Err(ErrorKind::GenericError("statement end date is before the start date".to_string()).into());
+    }
+    let end_date = to + Duration::days(1);
+
+    let mut periods = Vec::new();
+    let mut n = 0;
+    let mut start = from;
+    while start < end_date {
+        n += 1;
+        let end = std::cmp::min(interval.nth(from, n), end_date);
+        let (start_ts, end_ts) = (start_of_day(start), start_of_day(end));
+
+        let (opening_balance, _) = balance_at(txs, start_ts);
+        let (closing_balance, confirmed) = balance_at(txs, end_ts);
+
+        let (mut received, mut sent, mut fees) = (0u64, 0u64, 0u64);
+        for tx in txs.iter().filter(|tx| !is_cancelled(tx) && tx.creation_ts >= start_ts && tx.creation_ts < end_ts) {
+            let net = net_amount(tx);
+            if net >= 0 {
+                received += net as u64;
+            } else {
+                let fee = if tx.tx_type == TxLogEntryType::TxSent { tx.fee.unwrap_or(0) } else { 0 };
+                fees += fee;
+                sent += (-net) as u64 - std::cmp::min(fee, (-net) as u64);
+            }
+        }
+
+        periods.push(StatementPeriod {
+            start: start.format("%Y-%m-%d").to_string(),
+            end: (end - Duration::days(1)).format("%Y-%m-%d").to_string(),
+            opening_balance,
+            received,
+            sent,
+            fees,
+            closing_balance,
+            confirmed,
+            pending: closing_balance - confirmed,
+        });
+        start = end;
+    }
+    Ok(periods)
+}
+
+pub fn signed_amount_to_hr_string(amount: i64) -> String {
+    match amount >= 0 {
+        true => amount_to_hr_string(amount as u64, true),
+        false => format!("-{}", amount_to_hr_string((-amount) as u64, true)),
+    }
+}
+
+const CSV_HEADER: &'static str = "start,end,opening balance,received,sent,fees,closing balance,confirmed,pending";
+
+pub fn to_csv(periods: &Vec<StatementPeriod>) -> String {
+    let mut res = format!("{}\r\n", CSV_HEADER);
+    for p in periods {
+        let fields = vec![
+            p.start.clone(),
+            p.end.clone(),
+            signed_amount_to_hr_string(p.opening_balance),
+            amount_to_hr_string(p.received, true),
+            amount_to_hr_string(p.sent, true),
+            amount_to_hr_string(p.fees, true),
+            signed_amount_to_hr_string(p.closing_balance),
+            signed_amount_to_hr_string(p.confirmed),
+            signed_amount_to_hr_string(p.pending),
+        ];
+        res += &fields.iter().map(|f| csv_field(f)).collect::<Vec<String>>().join(",");
+        res += "\r\n";
+    }
+    res
+}
+
+pub fn print_table(periods: &Vec<StatementPeriod>) {
+    println!("{:<10}  {:<10}  {:>16}  {:>16}  {:>16}  {:>12}  {:>16}  {:>16}  {:>16}",
+             "Start", "End", "Opening", "Received", "Sent", "Fees", "Closing", "Confirmed", "Pending");
+    for p in periods {
+        println!("{:<10}  {:<10}  {:>16}  {:>16}  {:>16}  {:>12}  {:>16}  {:>16}  {:>16}",
+                 p.start,
+                 p.end,
+                 signed_amount_to_hr_string(p.opening_balance),
+                 amount_to_hr_string(p.received, true),
+                 amount_to_hr_string(p.sent, true),
+                 amount_to_hr_string(p.fees, true),
+                 signed_amount_to_hr_string(p.closing_balance),
+                 signed_amount_to_hr_string(p.confirmed),
+                 signed_amount_to_hr_string(p.pending));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use grin_keychain::Identifier;
+
+    const MWC: u64 = 1_000_000_000;
+
+    fn ts(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        Utc.ymd(y, m, d).and_hms(12, 0, 0)
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd(y, m, d)
+    }
+
+    fn tx(tx_type: TxLogEntryType, created: DateTime<Utc>, confirmed: Option<DateTime<Utc>>, credited: u64, debited: u64, fee: Option<u64>) -> TxLogEntry {
+        let mut tx = TxLogEntry::new(Identifier::zero(), tx_type, 0);
+        tx.creation_ts = created;
+        tx.confirmed = confirmed.is_some();
+        tx.confirmation_ts = confirmed;
+        tx.amount_credited = credited;
+        tx.amount_debited = debited;
+        tx.fee = fee;
+        tx
+    }
+
+    #[test]
+    fn month_end_clamping() {
+        assert_eq!(add_months(date(2021, 1, 31), 1), date(2021, 2, 28));
+        assert_eq!(add_months(date(2020, 1, 31), 1), date(2020, 2, 29));
+        assert_eq!(add_months(date(2021, 12, 15), 1), date(2022, 1, 15));
+        assert_eq!(add_months(date(2020, 2, 29), 12), date(2021, 2, 28));
+        // The day of the first interval is kept, not the clamped one
+        assert_eq!(Interval::Month.nth(date(2021, 1, 31), 2), date(2021, 3, 31));
+    }
+
+    #[test]
+    fn interval_boundaries() {
+        let periods = build_statement(&[], date(2021, 1, 31), date(2021, 4, 10), Interval::Month).unwrap();
+        let bounds: Vec<(&str, &str)> = periods.iter().map(|p| (p.start.as_str(), p.end.as_str())).collect();
+        assert_eq!(bounds, vec![
+            ("2021-01-31", "2021-02-27"),
+            ("2021-02-28", "2021-03-30"),
+            ("2021-03-31", "2021-04-10"),
+        ]);
+
+        let periods = build_statement(&[], date(2021, 1, 1), date(2021, 1, 10), Interval::Week).unwrap();
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[1].start, "2021-01-08");
+        assert_eq!(periods[1].end, "2021-01-10");
+
+        assert!(build_statement(&[], date(2021, 1, 2), date(2021, 1, 1), Interval::Day).is_err());
+    }
+
+    #[test]
+    fn balances() {
+        let txs = vec![
+            tx(TxLogEntryType::TxReceived, ts(2020, 12, 20), Some(ts(2020, 12, 20)), 10 * MWC, 0, None),
+            // Send of 3 MWC with 1 MWC change and 0.1 fee
+            tx(TxLogEntryType::TxSent, ts(2021, 1, 5), Some(ts(2021, 1, 6)), MWC, 4 * MWC + MWC / 10, Some(MWC / 10)),
+            tx(TxLogEntryType::TxReceivedCancelled, ts(2021, 1, 7), None, 50 * MWC, 0, None),
+            // Confirmed only in the next month
+            tx(TxLogEntryType::TxReceived, ts(2021, 1, 31), Some(ts(2021, 2, 1)), 2 * MWC, 0, None),
+        ];
+
+        let periods = build_statement(&txs, date(2021, 1, 1), date(2021, 3, 31), Interval::Month).unwrap();
+        assert_eq!(periods.len(), 3);
+
+        let jan = &periods[0];
+        assert_eq!(jan.opening_balance, 10 * MWC as i64);
+        assert_eq!(jan.received, 2 * MWC);
+        assert_eq!(jan.sent, 3 * MWC);
+        assert_eq!(jan.fees, MWC / 10);
+        assert_eq!(jan.closing_balance, (9 * MWC - MWC / 10) as i64);
+        assert_eq!(jan.pending, 2 * MWC as i64);
+
+        let feb = &periods[1];
+        assert_eq!(feb.opening_balance, jan.closing_balance);
+        assert_eq!(feb.pending, 0);
+
+        // A period without transactions keeps the balance
+        let mar = &periods[2];
+        assert_eq!((mar.received, mar.sent, mar.fees), (0, 0, 0));
+        assert_eq!(mar.opening_balance, mar.closing_balance);
+        assert_eq!(mar.closing_balance, jan.closing_balance);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use grin_core::core::amount_to_hr_string;
+use grin_core::libtx::tx_fee;
+use grin_wallet_libwallet::{OutputData, OutputStatus, TxLogEntry, TxLogEntryType};
+
+use super::tx_export::tx_type_name;
+
+// Number of the counterparties to show
+const TOP_COUNTERPARTIES: usize = 10;
+
+/// Volume and fees of the month. Amounts are in nanoMWC.
+#[derive(Debug, Clone, Default)]
+pub struct MonthStats {
+    pub received: u64,
+    pub sent: u64,
+    pub fees: u64,
+    pub tx_count: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CounterpartyStats {
+    pub address: String,
+    pub received: u64,
+    pub sent: u64,
+    pub tx_count: usize,
+}
+
+/// Summary of the wallet activity, built from the transaction log and the outputs
+#[derive(Debug, Clone, Default)]
+pub struct WalletStats {
+    /// Key: transaction type name
+    pub tx_counts: BTreeMap<&'static str, usize>,
+    /// Key: YYYY-MM
+    pub months: BTreeMap<String, MonthStats>,
+    /// Average time between creation and confirmation, seconds
+    pub avg_confirmation_secs: Option<i64>,
+    pub output_count: usize,
+    /// Key: upper bound of the bucket in nanoMWC, value: number of outputs
+    pub output_sizes: BTreeMap<u64, usize>,
+    /// Fee of the smallest transaction that spends one output
+    pub dust_threshold: u64,
+    pub dust_count: usize,
+    pub dust_value: u64,
+    /// Sorted by the total volume, largest first
+    pub counterparties: Vec<CounterpartyStats>,
+}
+
+// Buckets are powers of 10, from 0.001 MWC
+fn size_bucket(value: u64) -> u64 {
+    let mut bound = 1_000_000;
+    while value >= bound && bound < u64::max_value() / 10 {
+        bound *= 10;
+    }
+    bound
+}
+
+impl WalletStats {
+    pub fn new(txs: &[TxLogEntry], outputs: &[OutputData]) -> Self {
+        let mut stats = WalletStats::default();
+
+        let mut confirmation_secs = Vec::new();
+        let mut counterparties: BTreeMap<String, CounterpartyStats> = BTreeMap::new();
+
+        for tx in txs {
+            *stats.tx_counts.entry(tx_type_name(&tx.tx_type)).or_insert(0) += 1;
+            if tx.tx_type == TxLogEntryType::TxSentCancelled || tx.tx_type == TxLogEntryType::TxReceivedCancelled {
+                continue;
+            }
+
+            let fee = if tx.tx_type == TxLogEntryType::TxSent { tx.fee.unwrap_or(0) } else { 0 };
+            let (received, sent) = if tx.amount_credited >= tx.amount_debited {
+                (tx.amount_credited - tx.amount_debited, 0)
+            } else {
+                (0, (tx.amount_debited - tx.amount_credited).saturating_sub(fee))
+            };
+
+            let month = stats.months.entry(tx.creation_ts.format("%Y-%m").to_string()).or_insert(MonthStats::default());
+            month.received += received;
+            month.sent += sent;
+            month.fees += fee;
+            month.tx_count += 1;
+
+            // Coinbase confirmation time depends on the mining, not on the counterparty
+            if tx.tx_type != TxLogEntryType::ConfirmedCoinbase {
+                if let Some(ts) = tx.confirmation_ts {
+                    let secs = ts.signed_duration_since(tx.creation_ts).num_seconds();
+                    if secs >= 0 {
+                        confirmation_secs.push(secs);
+                    }
+                }
+            }
+
+            if let Some(address) = &tx.address {
+                let c = counterparties.entry(address.clone()).or_insert(CounterpartyStats {
+                    address: address.clone(),
+                    ..CounterpartyStats::default()
+                });
+                c.received += received;
+                c.sent += sent;
+                c.tx_count += 1;
+            }
+        }
+
+        if !confirmation_secs.is_empty() {
+            stats.avg_confirmation_secs = Some(confirmation_secs.iter().sum::<i64>() / confirmation_secs.len() as i64);
+        }
+
+        let mut counterparties: Vec<CounterpartyStats> = counterparties.into_iter().map(|(_, c)| c).collect();
+        counterparties.sort_by(|a, b| (b.received + b.sent).cmp(&(a.received + a.sent)));
+        counterparties.truncate(TOP_COUNTERPARTIES);
+        stats.counterparties = counterparties;
+
+        stats.dust_threshold = tx_fee(1, 1, 1, None);
+        for o in outputs.iter().filter(|o| o.status == OutputStatus::Unspent || o.status == OutputStatus::Unconfirmed) {
+            stats.output_count += 1;
+            *stats.output_sizes.entry(size_bucket(o.value)).or_insert(0) += 1;
+            if o.value < stats.dust_threshold {
+                stats.dust_count += 1;
+                stats.dust_value += o.value;
+            }
+        }
+
+        stats
+    }
+
+    pub fn print(&self) {
+        println!("Transactions:");
+        for (tx_type, count) in &self.tx_counts {
+            println!("    {:<18} {:>8}", tx_type, count);
+        }
+
+        println!("\nMonthly activity:");
+        println!("    {:<8}  {:>6}  {:>16}  {:>16}  {:>12}", "Month", "Txs", "Received", "Sent", "Fees");
+        for (month, m) in &self.months {
+            println!("    {:<8}  {:>6}  {:>16}  {:>16}  {:>12}",
+                     month,
+                     m.tx_count,
+                     amount_to_hr_string(m.received, true),
+                     amount_to_hr_string(m.sent, true),
+                     amount_to_hr_string(m.fees, true));
+        }
+
+        match self.avg_confirmation_secs {
+            Some(secs) => println!("\nAverage confirmation time: {}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60),
+            None => println!("\nAverage confirmation time: unknown"),
+        }
+
+        println!("\nOutputs: {}", self.output_count);
+        for (upper, count) in &self.output_sizes {
+            let lower = if *upper > 1_000_000 { upper / 10 } else { 0 };
+            println!("    {:>14} - {:<14} {:>8}", amount_to_hr_string(lower, true), amount_to_hr_string(*upper, true), count);
+        }
+        println!("Dust outputs (worth less than {} to spend): {}, total {}",
+                 amount_to_hr_string(self.dust_threshold, true),
+                 self.dust_count,
+                 amount_to_hr_string(self.dust_value, true));
+
+        if !self.counterparties.is_empty() {
+            println!("\nLargest counterparties:");
+            println!("    {:<64}  {:>6}  {:>16}  {:>16}", "Address", "Txs", "Received", "Sent");
+            for c in &self.counterparties {
+                println!("    {:<64}  {:>6}  {:>16}  {:>16}",
+                         c.address,
+                         c.tx_count,
+                         amount_to_hr_string(c.received, true),
+                         amount_to_hr_string(c.sent, true));
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs::File;
+use std::io::Write;
+
+use grin_core::core::amount_to_hr_string;
+use grin_wallet_libwallet::{TxLogEntry, TxLogEntryType};
+
+use common::{Error, ErrorKind};
+use super::tx_notes::TxNote;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(ErrorKind::GenericError(format!("invalid export format `{}`, use csv or json", s)).into()),
+        }
+    }
+
+    /// Format from the file extension, csv if it is not json
+    pub fn from_file_name(file_name: &str) -> Self {
+        match file_name.to_lowercase().ends_with(".json") {
+            true => ExportFormat::Json,
+            false => ExportFormat::Csv,
+        }
+    }
+}
+
+/// Exported transaction. Amounts are in nanoMWC, net amount is negative for the sends.
+#[derive(Serialize, Debug, Clone)]
+pub struct TxExportRow {
+    pub id: u32,
+    pub uuid: Option<String>,
+    pub tx_type: String,
+    pub account: String,
+    pub counterparty: Option<String>,
+    pub created: String,
+    pub confirmed: Option<String>,
+    pub confirmed_height: Option<u64>,
+    pub net_amount: i64,
+    pub fee: Option<u64>,
+    pub messages: Vec<String>,
+    pub has_proof: bool,
+    pub note: String,
+    pub tags: Vec<String>,
+}
+
+const CSV_HEADER: &'static str = "id,uuid,type,account,counterparty,created,confirmed,confirmed height,net amount,fee,messages,proof,note,tags";
+
+// TxLogEntryType print doesn't work for us
+pub fn tx_type_name(tx_type: &TxLogEntryType) -> &'static str {
+    match tx_type {
+        TxLogEntryType::ConfirmedCoinbase => "Coinbase",
+        TxLogEntryType::TxReceived => "Received",
+        TxLogEntryType::TxSent => "Sent",
+        TxLogEntryType::TxReceivedCancelled => "ReceivedCancelled",
+        TxLogEntryType::TxSentCancelled => "SentCancelled",
+    }
+}
+
+impl TxExportRow {
+    pub fn new(tx: &TxLogEntry, account: &str, has_proof: bool, note: Option<&TxNote>) -> Self {
+        TxExportRow {
+            id: tx.id,
+            uuid: tx.tx_slate_id.map(|uuid| uuid.to_string()),
+            tx_type: tx_type_name(&tx.tx_type).to_string(),
+            account: account.to_string(),
+            counterparty: tx.address.clone(),
+            created: tx.creation_ts.format("%Y-%m-%d %H:%M:%S").to_string(),
+            confirmed: tx.confirmation_ts.map(|ts| ts.format("%Y-%m-%d %H:%M:%S").to_string()),
+            confirmed_height: if tx.confirmed { Some(tx.output_height) } else { None },
+            net_amount: tx.amount_credited as i64 - tx.amount_debited as i64,
+            fee: tx.fee,
+            messages: tx.messages.as_ref()
+                .map(|m| m.messages.iter().filter_map(|m| m.message.clone()).collect())
+                .unwrap_or(Vec::new()),
+            has_proof,
+            note: note.map(|n| n.note.clone()).unwrap_or(String::new()),
+            tags: note.map(|n| n.tags.iter().cloned().collect()).unwrap_or(Vec::new()),
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let net_amount = match self.net_amount >= 0 {
+            true => amount_to_hr_string(self.net_amount as u64, true),
+            false => format!("-{}", amount_to_hr_string((-self.net_amount) as u64, true)),
+        };
+        let fields = vec![
+            self.id.to_string(),
+            self.uuid.clone().unwrap_or(String::new()),
+            self.tx_type.clone(),
+            self.account.clone(),
+            self.counterparty.clone().unwrap_or(String::new()),
+            self.created.clone(),
+            self.confirmed.clone().unwrap_or(String::new()),
+            self.confirmed_height.map(|h| h.to_string()).unwrap_or(String::new()),
+            net_amount,
+            self.fee.map(|fee| amount_to_hr_string(fee, true)).unwrap_or(String::new()),
+            self.messages.join("; "),
+            self.has_proof.to_string(),
+            self.note.clone(),
+            self.tags.join(" "),
+        ];
+        fields.iter().map(|f| csv_field(f)).collect::<Vec<String>>().join(",")
+    }
+}
+
+// Quote the field if it has a separator, quote or line break, quotes are doubled (RFC 4180)
+pub fn csv_field(value: &str) -> String {
+    if value.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn write_rows(file_name: &str, rows: &Vec<TxExportRow>, format: ExportFormat) -> Result<(), Error> {
+    let mut file = File::create(file_name)
+        .map_err(|e| ErrorKind::FileUnableToCreate(file_name.to_string(), format!("{}", e)))?;
+    match format {
+        ExportFormat::Csv => {
+            write!(file, "{}\r\n", CSV_HEADER)?;
+            for row in rows {
+                write!(file, "{}\r\n", row.to_csv())?;
+            }
+        }
+        ExportFormat::Json => {
+            file.write_all(serde_json::to_string_pretty(rows)?.as_bytes())?;
+        }
+    }
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::batch::parse_csv_line;
+
+    #[test]
+    fn plain_and_empty_fields() {
+        assert_eq!(csv_field("order 123"), "order 123");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn quoted_fields() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line 1\nline 2"), "\"line 1\nline 2\"");
+        assert_eq!(csv_field("line 1\r\nline 2"), "\"line 1\r\nline 2\"");
+    }
+
+    #[test]
+    fn round_trip() {
+        let fields = vec!["", "a,b", "say \"hi\", bye", "plain"];
+        let line = fields.iter().map(|f| csv_field(f)).collect::<Vec<String>>().join(",");
+        assert_eq!(parse_csv_line(&line), fields);
+    }
+}
@@ -0,0 +1,229 @@
+use chrono::{DateTime, Utc};
+use grin_wallet_libwallet::{TxLogEntry, TxLogEntryType};
+
+use common::{Error, ErrorKind};
+use super::tx_notes::TxNotes;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TxType {
+    Sent,
+    Received,
+    Coinbase,
+    Cancelled,
+}
+
+impl TxType {
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "sent" => Ok(TxType::Sent),
+            "received" => Ok(TxType::Received),
+            "coinbase" => Ok(TxType::Coinbase),
+            "cancelled" => Ok(TxType::Cancelled),
+            _ => Err(ErrorKind::GenericError(format!("invalid transaction type `{}`, use sent, received, coinbase or cancelled", s)).into()),
+        }
+    }
+
+    fn matches(&self, tx_type: &TxLogEntryType) -> bool {
+        match self {
+            TxType::Sent => *tx_type == TxLogEntryType::TxSent,
+            TxType::Received => *tx_type == TxLogEntryType::TxReceived,
+            TxType::Coinbase => *tx_type == TxLogEntryType::ConfirmedCoinbase,
+            TxType::Cancelled => *tx_type == TxLogEntryType::TxSentCancelled || *tx_type == TxLogEntryType::TxReceivedCancelled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TxSort {
+    Id,
+    Date,
+    Amount,
+    Height,
+}
+
+impl TxSort {
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "id" => Ok(TxSort::Id),
+            "date" => Ok(TxSort::Date),
+            "amount" => Ok(TxSort::Amount),
+            "height" => Ok(TxSort::Height),
+            _ => Err(ErrorKind::GenericError(format!("invalid sort order `{}`, use id, date, amount or height", s)).into()),
+        }
+    }
+}
+
+/// Filter and sort order for the transactions. None fields match any transaction.
+#[derive(Debug, Clone, Default)]
+pub struct TxFilter {
+    pub tx_type: Option<TxType>,
+    /// true - confirmed only, false - unconfirmed only
+    pub confirmed: Option<bool>,
+    pub from_date: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub to_date: Option<DateTime<Utc>>,
+    pub min_height: Option<u64>,
+    pub max_height: Option<u64>,
+    /// Amount is the absolute value of the balance change
+    pub min_amount: Option<u64>,
+    pub max_amount: Option<u64>,
+    /// Part of the counterparty address
+    pub address: Option<String>,
+    /// Part of any slate message, case insensitive
+    pub message: Option<String>,
+    pub has_proof: Option<bool>,
+    pub tag: Option<String>,
+    /// Part of the private note, case insensitive
+    pub note: Option<String>,
+    pub sort: Option<TxSort>,
+    pub reverse: bool,
+}
+
+/// Absolute value of the balance change of the transaction
+pub fn tx_amount(tx: &TxLogEntry) -> u64 {
+    if tx.amount_credited >= tx.amount_debited {
+        tx.amount_credited - tx.amount_debited
+    } else {
+        tx.amount_debited - tx.amount_credited
+    }
+}
+
+impl TxFilter {
+    /// True if the filter is not set, so the txs can be paginated by libwallet
+    pub fn is_empty(&self) -> bool {
+        self.tx_type.is_none() && self.confirmed.is_none() && self.from_date.is_none() && self.to_date.is_none()
+            && self.min_height.is_none() && self.max_height.is_none() && self.min_amount.is_none()
+            && self.max_amount.is_none() && self.address.is_none() && self.message.is_none()
+            && self.has_proof.is_none() && self.tag.is_none() && self.note.is_none()
+            && self.sort.is_none() && !self.reverse
+    }
+
+    pub fn matches(&self, tx: &TxLogEntry, has_proof: bool, tx_notes: &TxNotes) -> bool {
+        let amount = tx_amount(tx);
+
+        if let Some(tx_type) = &self.tx_type {
+            if !tx_type.matches(&tx.tx_type) {
+                return false;
+            }
+        }
+        if self.confirmed.map(|c| c != tx.confirmed).unwrap_or(false) {
+            return false;
+        }
+        if self.from_date.map(|d| tx.creation_ts < d).unwrap_or(false)
+            || self.to_date.map(|d| tx.creation_ts >= d).unwrap_or(false) {
+            return false;
+        }
+        // Height of the unconfirmed tx is 0 or stale, it is not confirmed within any heights
+        if (self.min_height.is_some() || self.max_height.is_some()) && !tx.confirmed {
+            return false;
+        }
+        if self.min_height.map(|h| tx.output_height < h).unwrap_or(false)
+            || self.max_height.map(|h| tx.output_height > h).unwrap_or(false) {
+            return false;
+        }
+        if self.min_amount.map(|a| amount < a).unwrap_or(false)
+            || self.max_amount.map(|a| amount > a).unwrap_or(false) {
+            return false;
+        }
+        if let Some(address) = &self.address {
+            if !tx.address.as_ref().map(|a| a.contains(address.as_str())).unwrap_or(false) {
+                return false;
+            }
+        }
+        if let Some(message) = &self.message {
+            let message = message.to_lowercase();
+            let found = tx.messages.as_ref()
+                .map(|m| m.messages.iter().any(|m| m.message.as_ref().map(|s| s.to_lowercase().contains(&message)).unwrap_or(false)))
+                .unwrap_or(false);
+            if !found {
+                return false;
+            }
+        }
+        if self.has_proof.map(|p| p != has_proof).unwrap_or(false) {
+            return false;
+        }
+        tx_notes.matches(tx, self.tag.as_ref().map(|t| t.as_str()), self.note.as_ref().map(|t| t.as_str()))
+    }
+
+    /// Sort the transactions, (tx, has proof) pairs, in the order of the filter
+    pub fn sort(&self, txs: &mut Vec<(TxLogEntry, bool)>) {
+        match self.sort.unwrap_or(TxSort::Id) {
+            TxSort::Id => txs.sort_by_key(|t| t.0.id),
+            TxSort::Date => txs.sort_by_key(|t| t.0.creation_ts),
+            TxSort::Amount => txs.sort_by_key(|t| tx_amount(&t.0)),
+            TxSort::Height => txs.sort_by_key(|t| t.0.output_height),
+        }
+        if self.reverse {
+            txs.reverse();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use grin_keychain::Identifier;
+
+    fn tx(tx_type: TxLogEntryType, confirmed: bool, height: u64, credited: u64, debited: u64) -> TxLogEntry {
+        let mut tx = TxLogEntry::new(Identifier::zero(), tx_type, 0);
+        tx.creation_ts = Utc.ymd(2021, 3, 15).and_hms(12, 0, 0);
+        tx.confirmed = confirmed;
+        tx.output_height = height;
+        tx.amount_credited = credited;
+        tx.amount_debited = debited;
+        tx
+    }
+
+    fn matches(filter: &TxFilter, tx: &TxLogEntry) -> bool {
+        filter.matches(tx, false, &TxNotes::default())
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = TxFilter::default();
+        assert!(filter.is_empty());
+        assert!(matches(&filter, &tx(TxLogEntryType::TxSent, false, 0, 0, 5)));
+        assert!(matches(&filter, &tx(TxLogEntryType::TxReceivedCancelled, false, 0, 5, 0)));
+    }
+
+    #[test]
+    fn type_and_confirmation() {
+        let filter = TxFilter { tx_type: Some(TxType::Cancelled), ..TxFilter::default() };
+        assert!(matches(&filter, &tx(TxLogEntryType::TxSentCancelled, false, 0, 0, 5)));
+        assert!(matches(&filter, &tx(TxLogEntryType::TxReceivedCancelled, false, 0, 5, 0)));
+        assert!(!matches(&filter, &tx(TxLogEntryType::TxSent, false, 0, 0, 5)));
+
+        let filter = TxFilter { confirmed: Some(false), ..TxFilter::default() };
+        assert!(matches(&filter, &tx(TxLogEntryType::TxSent, false, 0, 0, 5)));
+        assert!(!matches(&filter, &tx(TxLogEntryType::TxSent, true, 100, 0, 5)));
+    }
+
+    #[test]
+    fn heights_require_confirmation() {
+        let filter = TxFilter { min_height: Some(100), max_height: Some(200), ..TxFilter::default() };
+        assert!(matches(&filter, &tx(TxLogEntryType::TxReceived, true, 100, 5, 0)));
+        assert!(matches(&filter, &tx(TxLogEntryType::TxReceived, true, 200, 5, 0)));
+        assert!(!matches(&filter, &tx(TxLogEntryType::TxReceived, true, 201, 5, 0)));
+        assert!(!matches(&filter, &tx(TxLogEntryType::TxReceived, true, 99, 5, 0)));
+        // Stale height of the unconfirmed tx
+        assert!(!matches(&filter, &tx(TxLogEntryType::TxReceived, false, 150, 5, 0)));
+
+        let filter = TxFilter { max_height: Some(200), ..TxFilter::default() };
+        assert!(!matches(&filter, &tx(TxLogEntryType::TxSent, false, 0, 0, 5)));
+    }
+
+    #[test]
+    fn amounts_and_dates() {
+        // Amount is the balance change, 3 for the send of 3 with the change of 2
+        let send = tx(TxLogEntryType::TxSent, true, 100, 2, 5);
+        assert_eq!(tx_amount(&send), 3);
+        assert!(matches(&TxFilter { min_amount: Some(3), max_amount: Some(3), ..TxFilter::default() }, &send));
+        assert!(!matches(&TxFilter { min_amount: Some(4), ..TxFilter::default() }, &send));
+
+        let from = Utc.ymd(2021, 3, 15).and_hms(12, 0, 0);
+        assert!(matches(&TxFilter { from_date: Some(from), ..TxFilter::default() }, &send));
+        // to_date is exclusive
+        assert!(!matches(&TxFilter { to_date: Some(from), ..TxFilter::default() }, &send));
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use grin_wallet_libwallet::TxLogEntry;
+use common::{json_store, Error};
+
+const TX_NOTES_FILE: &'static str = "tx_notes.json";
+
+/// Private note and tags of the transaction. Never shared with the counterparty.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TxNote {
+    pub note: String,
+    pub tags: BTreeSet<String>,
+}
+
+impl TxNote {
+    pub fn is_empty(&self) -> bool {
+        self.note.is_empty() && self.tags.is_empty()
+    }
+}
+
+/// Notes and tags of the transactions, stored in the wallet data dir
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TxNotes {
+    /// Key: see tx_key
+    pub notes: BTreeMap<String, TxNote>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Transaction key for the notes. Tx log ids are per account, so slate id is used when it exists.
+pub fn tx_key(tx: &TxLogEntry) -> String {
+    match tx.tx_slate_id {
+        Some(uuid) => uuid.to_string(),
+        None => format!("{}/{}", tx.parent_key_id.to_hex(), tx.id),
+    }
+}
+
+impl TxNotes {
+    /// Load notes from the wallet data dir. Empty if nothing was stored yet.
+    pub fn open(data_dir: &str) -> Result<Self, Error> {
+        let path = Path::new(data_dir).join(TX_NOTES_FILE);
+        let mut tx_notes: TxNotes = json_store::load(&path, "transaction notes")?
+            .unwrap_or_default();
+        tx_notes.path = path;
+        Ok(tx_notes)
+    }
+
+    pub fn get(&self, tx: &TxLogEntry) -> Option<&TxNote> {
+        self.notes.get(&tx_key(tx))
+    }
+
+    /// Set the note, empty note removes it
+    pub fn set_note(&mut self, tx: &TxLogEntry, note: &str) -> Result<(), Error> {
+        self.update(tx, |n| n.note = note.to_string())
+    }
+
+    pub fn add_tags(&mut self, tx: &TxLogEntry, tags: &Vec<String>) -> Result<(), Error> {
+        self.update(tx, |n| n.tags.extend(tags.iter().cloned()))
+    }
+
+    pub fn remove_tags(&mut self, tx: &TxLogEntry, tags: &Vec<String>) -> Result<(), Error> {
+        self.update(tx, |n| n.tags.retain(|t| !tags.contains(t)))
+    }
+
+    /// True if the transaction has the tag and the note contains the text. None matches anything.
+    pub fn matches(&self, tx: &TxLogEntry, tag: Option<&str>, text: Option<&str>) -> bool {
+        let note = self.get(tx);
+        let tag_match = match tag {
+            Some(tag) => note.map(|n| n.tags.contains(tag)).unwrap_or(false),
+            None => true,
+        };
+        let text_match = match text {
+            Some(text) => note.map(|n| n.note.to_lowercase().contains(&text.to_lowercase())).unwrap_or(false),
+            None => true,
+        };
+        tag_match && text_match
+    }
+
+    fn update<F: FnOnce(&mut TxNote)>(&mut self, tx: &TxLogEntry, f: F) -> Result<(), Error> {
+        let key = tx_key(tx);
+        let mut note = self.notes.get(&key).cloned().unwrap_or(TxNote::default());
+        f(&mut note);
+        if note.is_empty() {
+            self.notes.remove(&key);
+        } else {
+            self.notes.insert(key, note);
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        json_store::save(&self.path, self)
+    }
+}
@@ -1,10 +1,13 @@
 use uuid::Uuid;
+use chrono::{NaiveDate, Utc};
+use std::collections::HashMap;
 use common::config::Wallet713Config;
-use common::{ErrorKind, Error};
+use common::{json_store, ErrorKind, Error};
 
-use grin_wallet_libwallet::{Slate, TxLogEntry, WalletInst, OutputCommitMapping, ScannedBlockInfo, NodeClient, StatusMessage, AcctPathMapping};
+use grin_wallet_libwallet::{Slate, SlateVersion, VersionedSlate, TxLogEntry, TxLogEntryType, WalletInst, OutputCommitMapping, OutputData, OutputStatus, ScannedBlockInfo, NodeClient, StatusMessage, AcctPathMapping};
 use grin_wallet_impls::lifecycle::WalletSeed;
 use grin_core::core::Transaction;
+use grin_core::libtx::tx_fee;
 use grin_util::secp::key::{ SecretKey, PublicKey };
 use grin_wallet_impls::node_clients::HTTPNodeClient;
 use grin_keychain::keychain::ExtKeychain;
@@ -12,17 +15,34 @@ use crate::common::{Arc, Mutex};
 
 use grin_wallet_libwallet::proof::tx_proof::TxProof;
 use crate::wallet::api::api;
+use crate::wallet::account_archive::AccountArchive;
+use crate::wallet::coin_control::CoinControl;
+use crate::wallet::invoice_queue::{self, InvoiceQueue, QueuedInvoice};
+use crate::wallet::payment_requests::{self, PaymentRequest, PaymentRequests};
+use crate::wallet::selection;
+use crate::wallet::stats::WalletStats;
+use crate::wallet::statement::{self, Interval, StatementPeriod};
+use crate::wallet::tx_export::{self, ExportFormat, TxExportRow};
+use crate::wallet::tx_filter::TxFilter;
+use crate::wallet::tx_notes::{TxNote, TxNotes};
 use grin_util::ZeroingString;
 use grin_wallet_impls::{DefaultWalletImpl, DefaultLCProvider};
 use grin_wallet_controller::display;
 use std::sync::atomic::{AtomicBool, Ordering};
 use grin_wallet_libwallet::api_impl::owner_updater;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::thread;
 use std::thread::JoinHandle;
 use std::sync::mpsc::Sender;
+use std::fs::create_dir_all;
+use std::path::Path;
 use grin_wallet_libwallet::proof::proofaddress::{ProvableAddress,ProofAddressType};
 
+// Invoice slates passed as files, by slate id
+const INVOICE_SLATES_DIR: &'static str = "invoice_slates";
+// Heights of the last policy reposts, by slate id
+const TX_POLICY_REPOSTS_FILE: &'static str = "tx_policy_reposts.json";
+
 pub struct Wallet {
     backend: Option< Arc<Mutex<Box<dyn WalletInst<'static,
         DefaultLCProvider<'static, HTTPNodeClient, ExtKeychain>,
@@ -199,9 +219,49 @@ impl Wallet {
         Ok(api::get_current_account(self.get_wallet_instance()?)?)
     }
 
-    pub fn list_accounts(&self) -> Result<(), Error> {
-        let acct_mappings = api::accounts(self.get_wallet_instance()?)?;
-        display::accounts(acct_mappings);
+    pub fn list_accounts(&self, confirmations: u64, show_archived: bool) -> Result<(), Error> {
+        self.accounts_info(false, confirmations, show_archived)
+    }
+
+    // Print balances of every account and the wallet total. Archived and deleted accounts are listed
+    // only if show_archived is set.
+    pub fn accounts_info(&self, refresh: bool, confirmations: u64, show_archived: bool) -> Result<(), Error> {
+        let wallet_inst = self.get_wallet_instance()?;
+        let (validated, accounts) = api::retrieve_accounts_info(wallet_inst.clone(), refresh, confirmations)?;
+        let current = self.get_current_account()?.label;
+        let archive = self.account_archive()?;
+        let hidden = accounts.iter().filter(|(acct, _)| archive.is_hidden(acct)).count();
+
+        let hr = |amount: u64| grin_core::core::amount_to_hr_string(amount, true);
+        println!("   {:<20}  {:<14}  {:>16}  {:>16}  {:>16}  {:>16}",
+                 "Name", "Path", "Spendable", "Awaiting conf.", "Locked", "Total");
+        let (mut spendable, mut awaiting, mut locked, mut total) = (0, 0, 0, 0);
+        for (acct, info) in accounts.iter().filter(|(acct, _)| show_archived || !archive.is_hidden(acct)) {
+            // Immature coinbase is waiting for the confirmations too
+            let acct_awaiting = info.amount_awaiting_confirmation + info.amount_immature;
+            println!(" {} {:<20}  {:<14}  {:>16}  {:>16}  {:>16}  {:>16}  {}",
+                     if acct.label == current { "*" } else { " " },
+                     acct.label,
+                     acct.path.to_bip_32_string(),
+                     hr(info.amount_currently_spendable),
+                     hr(acct_awaiting),
+                     hr(info.amount_locked),
+                     hr(info.total),
+                     if archive.is_deleted(acct) { "deleted" } else if archive.is_archived(acct) { "archived" } else { "" });
+            spendable += info.amount_currently_spendable;
+            awaiting += acct_awaiting;
+            locked += info.amount_locked;
+            total += info.total;
+        }
+        println!("   {:<20}  {:<14}  {:>16}  {:>16}  {:>16}  {:>16}",
+                 "Total", "", hr(spendable), hr(awaiting), hr(locked), hr(total));
+        if hidden > 0 && !show_archived {
+            println!("{} archived or deleted account(s) are not listed, use `accounts --archived` to see them", hidden);
+        }
+
+        if refresh && !validated {
+            println!("WARNING: wallet was not refreshed from the node, data might be outdated");
+        }
         Ok(())
     }
 
@@ -216,10 +276,83 @@ impl Wallet {
     }
 
     pub fn switch_account(&mut self, name: &str) -> Result<(), Error> {
+        if let Some(acct) = self.account_path(name)? {
+            self.account_archive()?.check_can_receive(&acct)?;
+        }
         api::set_current_account(self.get_wallet_instance()?, name)?;
         Ok(())
     }
 
+    fn account_archive(&self) -> Result<AccountArchive, Error> {
+        AccountArchive::open(&self.get_data_dir()?)
+    }
+
+    // Account that can be archived or deleted: it exists and it is not the active or the default account
+    fn account_to_hide(&mut self, name: &str) -> Result<AcctPathMapping, Error> {
+        let acct = self.account_path(name)?
+            .ok_or(ErrorKind::GenericError(format!("Account `{}` not found", name)))?;
+        if acct.label == "default" {
+            return Err(ErrorKind::GenericError("The default account can't be archived or deleted".to_string()).into());
+        }
+        if acct.label == self.get_current_account()?.label {
+            return Err(ErrorKind::GenericError(format!("Account `{}` is active, switch to another account first", name)).into());
+        }
+        if grin_wallet_libwallet::get_receive_account().as_ref() == Some(&acct.label) {
+            return Err(ErrorKind::GenericError(format!("Account `{}` receives the incoming funds, change it with `set-recv` first", name)).into());
+        }
+        Ok(acct)
+    }
+
+    // Hide the account from the listings and refuse to receive into it
+    pub fn archive_account(&mut self, name: &str) -> Result<(), Error> {
+        let acct = self.account_to_hide(name)?;
+        self.account_archive()?.archive(&acct)
+    }
+
+    // Delete the account with no funds and no pending transactions. libwallet can't remove the account,
+    // so it is hidden like an archived one.
+    pub fn delete_account(&mut self, name: &str) -> Result<(), Error> {
+        let acct = self.account_to_hide(name)?;
+        let wallet_inst = self.get_wallet_instance()?;
+
+        let (_, accounts) = api::retrieve_accounts_info(wallet_inst.clone(), false, 1)?;
+        let balance = accounts.iter().find(|(a, _)| a.path == acct.path).map(|(_, info)| info.total).unwrap_or(0);
+        if balance > 0 {
+            return Err(ErrorKind::GenericError(format!("Account `{}` has balance {}, it can't be deleted",
+                name, grin_core::core::amount_to_hr_string(balance, true))).into());
+        }
+        let pending = api::retrieve_account_txs(wallet_inst, Some(acct.label.clone()))?
+            .into_iter()
+            .filter(|tx| !tx.confirmed && tx.tx_type != TxLogEntryType::TxSentCancelled && tx.tx_type != TxLogEntryType::TxReceivedCancelled)
+            .count();
+        if pending > 0 {
+            return Err(ErrorKind::GenericError(format!("Account `{}` has {} pending transaction(s), it can't be deleted", name, pending)).into());
+        }
+
+        self.account_archive()?.delete(&acct)
+    }
+
+    // Restore the archived or deleted account
+    pub fn unarchive_account(&mut self, name: &str) -> Result<(), Error> {
+        let acct = self.account_path(name)?
+            .ok_or(ErrorKind::GenericError(format!("Account `{}` not found", name)))?;
+        self.account_archive()?.unarchive(&acct)
+    }
+
+    // Account that receives a slate: dest_acct_name, the `set-recv` account or the active one.
+    // Error if it doesn't exist or is archived.
+    pub fn check_receive_account(&self, dest_acct_name: Option<&str>) -> Result<(), Error> {
+        let name = match dest_acct_name.map(|n| n.to_string()).or(grin_wallet_libwallet::get_receive_account()) {
+            Some(name) => name,
+            None => self.get_current_account()?.label,
+        };
+        let acct = api::accounts(self.get_wallet_instance()?)?
+            .into_iter()
+            .find(|a| a.label == name)
+            .ok_or(ErrorKind::GenericError(format!("Account `{}` not found", name)))?;
+        self.account_archive()?.check_can_receive(&acct)
+    }
+
     pub fn info(&self, refresh: bool, confirmations: u64) -> Result<(), Error> {
         let (mut validated, wallet_info) = api::retrieve_summary_info(
             self.get_wallet_instance()?, refresh,
@@ -236,6 +369,16 @@ impl Wallet {
         Ok(id)
     }
 
+    // Tx log id of the sender side of the slate in the account, None is the active account. Self sends
+    // have the sent and the received entries with the same slate id, get_id returns the received one.
+    pub fn get_sent_tx_id(&self, account: Option<String>, slate_id: Uuid) -> Result<u32, Error> {
+        api::retrieve_account_txs(self.get_wallet_instance()?, account)?
+            .into_iter()
+            .find(|tx| tx.tx_slate_id == Some(slate_id) && tx.tx_type == TxLogEntryType::TxSent)
+            .map(|tx| tx.id)
+            .ok_or(ErrorKind::GenericError(format!("could not find the sent transaction for slate {}", slate_id)).into())
+    }
+
     pub fn txs_count(&self) -> Result<usize, Error> {
         let (_, txs) = api::retrieve_txs_with_proof_flag(self.get_wallet_instance()?, false, None, None, None, None)?;
         Ok(txs.len())
@@ -248,8 +391,11 @@ impl Wallet {
                pagination_length: Option<u32>,
                tx_id: Option<u32>, // display single tx with all details
                tx_slate_id: Option<Uuid>,
+               filter: &TxFilter,
     ) -> Result<(), Error> {
         let wallet_inst = self.get_wallet_instance()?;
+        let tx_notes = self.tx_notes()?;
+        let filtered = !filter.is_empty();
 
         let height = if refresh_from_node {
             let (h, _) = api::node_height(wallet_inst.clone())?;
@@ -260,10 +406,24 @@ impl Wallet {
             w.last_confirmed_height()?
         };
 
+        // With filters the pagination is applied to the filtered transactions
         let (validated, txs) = api::retrieve_txs_with_proof_flag(
-                wallet_inst.clone(), refresh_from_node, tx_id.clone(),
-                tx_slate_id.clone(), pagination_start, pagination_length)?;
-        let txs = txs.iter().map(|tpl| tpl.0.clone()).collect::<Vec<TxLogEntry>>();
+                wallet_inst.clone(), refresh_from_node, tx_id.clone(), tx_slate_id.clone(),
+                if filtered { None } else { pagination_start },
+                if filtered { None } else { pagination_length })?;
+        let txs = if filtered {
+            let mut txs: Vec<(TxLogEntry, bool)> = txs.into_iter()
+                .filter(|(tx, has_proof)| filter.matches(tx, *has_proof, &tx_notes))
+                .collect();
+            filter.sort(&mut txs);
+            txs.into_iter()
+                .skip(pagination_start.unwrap_or(0) as usize)
+                .take(pagination_length.map(|l| l as usize).unwrap_or(usize::max_value()))
+                .map(|(tx, _)| tx)
+                .collect()
+        } else {
+            txs.into_iter().map(|(tx, _)| tx).collect::<Vec<TxLogEntry>>()
+        };
 
         let data_dir = {
             wallet_lock!(wallet_inst, w);
@@ -302,6 +462,17 @@ impl Wallet {
             },
         )?;
 
+        let noted: Vec<(u32, &TxNote)> = txs.iter()
+            .filter_map(|tx| tx_notes.get(tx).map(|n| (tx.id, n)))
+            .collect();
+        if !noted.is_empty() {
+            println!("Notes and tags:");
+            for (id, n) in noted {
+                let tags: Vec<String> = n.tags.iter().cloned().collect();
+                println!("    {:>5}  [{}]  {}", id, tags.join(", "), n.note);
+            }
+        }
+
         if txs.len()!=1 {
             return Ok(());
         }
@@ -321,6 +492,12 @@ impl Wallet {
         Ok(())
     }
 
+    pub fn get_data_dir(&self) -> Result<String, Error> {
+        let wallet_inst = self.get_wallet_instance()?;
+        wallet_lock!(wallet_inst, w);
+        Ok(String::from(w.get_data_file_dir()))
+    }
+
     pub fn txs_bulk_validate(&self, kernels_fn: &str, outputs_fn: &str, result_fn: &str )  -> Result<(), Error> {
         api::txs_bulk_validate(self.get_wallet_instance()?, kernels_fn, outputs_fn, result_fn )?;
         Ok(())
@@ -333,6 +510,7 @@ impl Wallet {
 
         let (height, _) = api::node_height(w.clone())?;
         let (_validated, outputs) = api::retrieve_outputs(w.clone(), false, refresh_from_node, None, None, None)?;
+        let output_list = &self.select_outputs(None, output_list)?;
 
         if output_list.is_some() {
             let ol = output_list.clone().unwrap();
@@ -369,6 +547,7 @@ impl Wallet {
         let (_validated, outputs) = api::retrieve_outputs(
                     wallet.clone(),false, refresh_from_node,
                     None, None, None)?;
+        let output_list = &self.select_outputs(None, output_list)?;
 
         if output_list.is_some() {
             let ol = output_list.clone().unwrap();
@@ -404,13 +583,205 @@ impl Wallet {
         };
 
         let (validated, outputs) = api::retrieve_outputs(wallet, show_spent, refresh_from_node, None, pagination_start, pagination_length)?;
+
+        let coin_control = self.coin_control()?;
+        let mut marked: Vec<String> = Vec::new();
+        for o in &outputs {
+            if let Some(commit) = &o.output.commit {
+                let frozen = coin_control.is_frozen(commit);
+                let label = coin_control.get_label(commit);
+                if frozen || label.is_some() {
+                    marked.push(format!("{} {:<6} {}", commit, if frozen { "FROZEN" } else { "" }, label.cloned().unwrap_or(String::new())));
+                }
+            }
+        }
+
         display::outputs(&self.get_current_account()?.label, height, !refresh_from_node || validated, outputs, true)?;
+
+        if !marked.is_empty() {
+            println!("Frozen and labeled outputs:");
+            for m in marked {
+                println!("    {}", m);
+            }
+        }
         Ok(())
     }
 
+    // Outputs of the active account that can be spent and are not frozen. Sorted by value, smallest first.
+    pub fn spendable_outputs(&self, refresh_from_node: bool, minimum_confirmations: u64) -> Result<Vec<OutputData>, Error> {
+        let wallet = self.get_wallet_instance()?;
+        let (height, _) = api::node_height(wallet.clone())?;
+        let (_validated, outputs) = api::retrieve_outputs(wallet, false, refresh_from_node, None, None, None)?;
+        let coin_control = self.coin_control()?;
+
+        let mut res: Vec<OutputData> = outputs.into_iter()
+            .map(|o| o.output)
+            .filter(|o| o.eligible_to_spend(height, minimum_confirmations))
+            .filter(|o| o.commit.as_ref().map(|c| !coin_control.is_frozen(c)).unwrap_or(false))
+            .collect();
+        res.sort_by_key(|o| o.value);
+        Ok(res)
+    }
+
+    // Inputs for the selection strategy: 'use all' flag and the list of outputs for libwallet.
+    // Strategies from the selection module pick the inputs here, libwallet then spends all of them.
+    fn strategy_inputs(
+        &self,
+        account: Option<String>,
+        selection_strategy: &str,
+        outputs: &Option<Vec<String>>,
+        amount: u64,
+        minimum_confirmations: u64,
+        change_outputs: u32,
+        max_outputs: u32,
+        routputs: usize,
+    ) -> Result<(bool, Option<Vec<String>>), Error> {
+        let strategy = match selection::get_strategy(selection_strategy) {
+            Some(strategy) => strategy,
+            None => return Ok((selection_strategy == "all", self.select_outputs(account, outputs)?)),
+        };
+
+        let candidates = self.strategy_candidates(account, minimum_confirmations)?;
+        let params = selection::SelectionParams {
+            amount,
+            change_outputs: change_outputs as usize,
+            routputs,
+            max_outputs: max_outputs as usize,
+        };
+        let inputs = strategy.select(&candidates, &params)
+            .ok_or(ErrorKind::NoInputsForStrategy(selection_strategy.to_string()))?;
+
+        Ok((true, Some(inputs.iter().filter_map(|c| c.output.commit.clone()).collect())))
+    }
+
+    // Same as strategy_inputs, but the inputs for sending everything
+    fn strategy_all_inputs(
+        &self,
+        account: Option<String>,
+        selection_strategy: &str,
+        outputs: &Option<Vec<String>>,
+        minimum_confirmations: u64,
+    ) -> Result<(bool, Option<Vec<String>>), Error> {
+        let strategy = match selection::get_strategy(selection_strategy) {
+            Some(strategy) => strategy,
+            None => return Ok((true, self.select_outputs(account, outputs)?)),
+        };
+
+        let candidates = self.strategy_candidates(account, minimum_confirmations)?;
+        let inputs = strategy.select_all(&candidates);
+        if inputs.is_empty() {
+            return Err(ErrorKind::NoInputsForStrategy(selection_strategy.to_string()).into());
+        }
+        Ok((true, Some(inputs.iter().filter_map(|c| c.output.commit.clone()).collect())))
+    }
+
+    // Spendable outputs of the account, frozen ones are excluded
+    fn strategy_candidates(&self, account: Option<String>, minimum_confirmations: u64) -> Result<Vec<selection::Candidate>, Error> {
+        let wallet = self.get_wallet_instance()?;
+        let (height, _) = api::node_height(wallet.clone())?;
+        let coin_control = self.coin_control()?;
+        let counterparties: HashMap<u32, Option<String>> = api::retrieve_account_txs(wallet.clone(), account.clone())?
+            .into_iter()
+            .map(|tx| (tx.id, tx.address))
+            .collect();
+
+        Ok(api::retrieve_account_outputs(wallet, account, false)?
+            .into_iter()
+            .map(|o| o.output)
+            .filter(|o| o.eligible_to_spend(height, minimum_confirmations))
+            .filter(|o| o.commit.as_ref().map(|c| !coin_control.is_frozen(c)).unwrap_or(false))
+            .map(|o| selection::Candidate {
+                counterparty: o.tx_log_entry.and_then(|id| counterparties.get(&id).cloned().unwrap_or(None)),
+                output: o,
+            })
+            .collect())
+    }
+
+    fn coin_control(&self) -> Result<CoinControl, Error> {
+        CoinControl::open(&self.get_data_dir()?)
+    }
+
+    // Freeze or unfreeze the output of the active account. Frozen outputs are never selected for the send.
+    pub fn freeze_output(&self, commit: &str, freeze: bool) -> Result<(), Error> {
+        let mut coin_control = self.coin_control()?;
+        if freeze {
+            self.find_output(commit)?;
+            coin_control.freeze(commit)
+        } else {
+            coin_control.unfreeze(commit)
+        }
+    }
+
+    // Set the label of the output, empty label removes it
+    pub fn label_output(&self, commit: &str, label: &str) -> Result<(), Error> {
+        self.find_output(commit)?;
+        self.coin_control()?.set_label(commit, label)
+    }
+
+    fn find_output(&self, commit: &str) -> Result<(), Error> {
+        let outputs = api::retrieve_account_outputs(self.get_wallet_instance()?, None, true)?;
+        if outputs.iter().any(|o| o.output.commit.as_ref().map(|c| c == commit).unwrap_or(false)) {
+            Ok(())
+        } else {
+            Err(ErrorKind::GenericError(format!("Output {} not found", commit)).into())
+        }
+    }
+
+    // Apply coin control to the send inputs. Items of the output_list can be commitments, output labels or ids of
+    // the transactions that created the outputs. Frozen outputs are always excluded. If nothing is frozen and
+    // output_list is None, None is returned, so the selection strategy works with all outputs.
+    fn select_outputs(&self, account: Option<String>, output_list: &Option<Vec<String>>) -> Result<Option<Vec<String>>, Error> {
+        let coin_control = self.coin_control()?;
+        if output_list.is_none() && coin_control.frozen.is_empty() {
+            return Ok(None);
+        }
+
+        let outputs = api::retrieve_account_outputs(self.get_wallet_instance()?, account, false)?;
+        let mut res: Vec<String> = Vec::new();
+
+        match output_list {
+            None => {
+                for o in &outputs {
+                    if let Some(commit) = &o.output.commit {
+                        if !coin_control.is_frozen(commit) {
+                            res.push(commit.clone());
+                        }
+                    }
+                }
+            },
+            Some(list) => {
+                for item in list {
+                    let tx_id = item.parse::<u32>().ok();
+                    let mut found = false;
+                    for o in &outputs {
+                        let commit = match &o.output.commit {
+                            Some(c) => c,
+                            None => continue,
+                        };
+                        let matched = commit == item
+                            || coin_control.get_label(commit) == Some(item)
+                            || (tx_id.is_some() && o.output.tx_log_entry == tx_id);
+                        if !matched || coin_control.is_frozen(commit) {
+                            continue;
+                        }
+                        found = true;
+                        if !res.contains(commit) {
+                            res.push(commit.clone());
+                        }
+                    }
+                    if !found {
+                        return Err(ErrorKind::GenericError(format!("No spendable outputs match `{}`, frozen outputs are excluded", item)).into());
+                    }
+                }
+            },
+        }
+        Ok(Some(res))
+    }
+
     // Create slate but not lock outptus into the DB. Call tx_lock_outputs to do that
     pub fn initiate_send_tx(
         &self,
+        active_account: Option<String>,
         address: Option<String>,
         amount: u64,
         minimum_confirmations: u64,
@@ -425,15 +796,17 @@ impl Wallet {
 	    ttl_blocks: u64,
         do_proof: bool,
     ) -> Result<Slate, Error> {
+        let (use_all, outputs) = self.strategy_inputs(active_account.clone(), selection_strategy, &outputs, amount,
+                                                      minimum_confirmations, change_outputs, max_outputs, routputs)?;
         let slate = api::initiate_tx(
             self.get_wallet_instance()?,
-            None,
+            active_account,
             address.clone(),
             amount,
             minimum_confirmations,
             max_outputs,
             change_outputs,
-            selection_strategy == "all",
+            use_all,
             message,
             outputs,
             version,
@@ -441,20 +814,310 @@ impl Wallet {
             status_send_channel,
             ttl_blocks,
             do_proof,
+            false,
+        )?;
+
+        Ok(slate)
+    }
+
+    // Estimate the send transaction, nothing is locked. Resulting slate has the fee and
+    // the total amount locked (sum of the inputs) as an amount.
+    pub fn estimate_send_tx(
+        &self,
+        active_account: Option<String>,
+        amount: u64,
+        minimum_confirmations: u64,
+        selection_strategy: &str,
+        change_outputs: u32,
+        max_outputs: u32,
+        outputs: Option<Vec<String>>,
+        routputs: usize,
+        status_send_channel: &Option<Sender<StatusMessage>>,
+    ) -> Result<Slate, Error> {
+        let (use_all, outputs) = self.strategy_inputs(active_account.clone(), selection_strategy, &outputs, amount,
+                                                      minimum_confirmations, change_outputs, max_outputs, routputs)?;
+        self.estimate_tx(active_account, amount, minimum_confirmations, max_outputs, change_outputs, use_all,
+                         outputs, routputs, status_send_channel)
+    }
+
+    // Estimate the transaction that spends everything the selection strategy allows, without change.
+    // Amount of the slate minus the fee is what `send ALL` sends.
+    pub fn estimate_send_all_tx(
+        &self,
+        active_account: Option<String>,
+        minimum_confirmations: u64,
+        selection_strategy: &str,
+        outputs: Option<Vec<String>>,
+        routputs: usize,
+        status_send_channel: &Option<Sender<StatusMessage>>,
+    ) -> Result<Slate, Error> {
+        let (use_all, outputs) = self.strategy_all_inputs(active_account.clone(), selection_strategy, &outputs,
+                                                          minimum_confirmations)?;
+        self.estimate_tx(active_account, 1, minimum_confirmations, 500, 0, use_all,
+                         outputs, routputs, status_send_channel)
+    }
+
+    fn estimate_tx(
+        &self,
+        active_account: Option<String>,
+        amount: u64,
+        minimum_confirmations: u64,
+        max_outputs: u32,
+        change_outputs: u32,
+        use_all: bool,
+        outputs: Option<Vec<String>>,
+        routputs: usize,
+        status_send_channel: &Option<Sender<StatusMessage>>,
+    ) -> Result<Slate, Error> {
+        let slate = api::initiate_tx(
+            self.get_wallet_instance()?,
+            active_account,
+            None,
+            amount,
+            minimum_confirmations,
+            max_outputs,
+            change_outputs,
+            use_all,
+            None,
+            outputs,
+            None,
+            routputs,
+            status_send_channel,
+            0,
+            false,
+            true,
         )?;
 
         Ok(slate)
     }
 
     // Create invoice transaction
-    pub fn initiate_receive_tx(&self, address: Option<String>, amount: u64, num_outputs: usize) -> Result<Slate, Error> {
+    pub fn initiate_receive_tx(&self, active_account: Option<String>, address: Option<String>, amount: u64, num_outputs: usize) -> Result<Slate, Error> {
         let slate = api::initiate_receive_tx(self.get_wallet_instance()?,
                                              address,
-                                             None,
+                                             active_account,
                                              amount, num_outputs, None)?;
         Ok(slate)
     }
 
+    // Keep the invoice slate that is passed as a file, finalize compares the response with it
+    pub fn store_invoice_slate(&self, slate: &Slate) -> Result<(), Error> {
+        let dir = Path::new(&self.get_data_dir()?).join(INVOICE_SLATES_DIR);
+        create_dir_all(&dir)?;
+        let versioned = VersionedSlate::into_version(slate.clone(), SlateVersion::V3);
+        json_store::save(&dir.join(format!("{}.json", slate.id)), &versioned)
+    }
+
+    // Invoice slate stored by store_invoice_slate, if there is one
+    pub fn get_invoice_slate(&self, slate_id: &Uuid) -> Result<Option<Slate>, Error> {
+        let path = Path::new(&self.get_data_dir()?).join(INVOICE_SLATES_DIR).join(format!("{}.json", slate_id));
+        let slate: Option<VersionedSlate> = json_store::load(&path, "invoice slate")?;
+        Ok(slate.map(|s| s.into()))
+    }
+
+    // Show unconfirmed sent and received transactions with their age, TTL and the suggested action
+    pub fn pending(&self, refresh_from_node: bool) -> Result<(), Error> {
+        let wallet = self.get_wallet_instance()?;
+        let (validated, pending) = api::retrieve_pending_txs(wallet.clone(), None, refresh_from_node)?;
+        let (height, _) = api::node_height(wallet)?;
+
+        if pending.is_empty() {
+            println!("No pending transactions");
+            return Ok(());
+        }
+
+        println!(
+            "{:>5}  {:<8}  {:>14}  {:>10}  {:>16}  {:>10}  {:<6}  {:<8}  {}",
+            "Id", "Type", "Amount", "Age,blocks", "Age", "TTL,blocks", "Stored", "Kernel", "Action"
+        );
+        for p in &pending {
+            let tx = &p.tx;
+            let amount = if tx.amount_credited >= tx.amount_debited {
+                grin_core::core::amount_to_hr_string(tx.amount_credited - tx.amount_debited, true)
+            } else {
+                format!("-{}", grin_core::core::amount_to_hr_string(tx.amount_debited - tx.amount_credited, true))
+            };
+            let age_blocks = tx.kernel_lookup_min_height
+                .map(|h| height.saturating_sub(h).to_string())
+                .unwrap_or("?".to_string());
+            let age = Utc::now().signed_duration_since(tx.creation_ts);
+            let age = format!("{}d {:02}h {:02}m", age.num_days(), age.num_hours() % 24, age.num_minutes() % 60);
+            let ttl_left = tx.ttl_cutoff_height.map(|ttl| ttl as i64 - height as i64);
+
+            let action = if p.kernel_height.is_some() {
+                "wait, kernel is on chain"
+            } else if ttl_left.map(|t| t <= 0).unwrap_or(false) {
+                "cancel, expired"
+            } else if tx.tx_type == TxLogEntryType::TxSent && p.has_stored_tx {
+                "repost"
+            } else if tx.tx_type == TxLogEntryType::TxSent {
+                "wait or cancel"
+            } else {
+                "wait for the sender or cancel"
+            };
+
+            println!(
+                "{:>5}  {:<8}  {:>14}  {:>10}  {:>16}  {:>10}  {:<6}  {:<8}  {}",
+                tx.id,
+                if tx.tx_type == TxLogEntryType::TxSent { "Sent" } else { "Received" },
+                amount,
+                age_blocks,
+                age,
+                ttl_left.map(|t| t.to_string()).unwrap_or("-".to_string()),
+                if p.has_stored_tx { "yes" } else { "no" },
+                p.kernel_height.map(|h| h.to_string()).unwrap_or("no".to_string()),
+                action
+            );
+        }
+        if refresh_from_node && !validated {
+            println!("WARNING: wallet was not refreshed from the node, data might be outdated");
+        }
+        Ok(())
+    }
+
+    fn tx_notes(&self) -> Result<TxNotes, Error> {
+        TxNotes::open(&self.get_data_dir()?)
+    }
+
+    // Set the private note of the transaction, empty note removes it
+    pub fn set_tx_note(&self, id: u32, note: &str) -> Result<(), Error> {
+        let (tx, _) = self.get_tx_with_height(false, Some(id), None)?;
+        self.tx_notes()?.set_note(&tx, note)
+    }
+
+    // Add or remove the private tags of the transaction
+    pub fn tag_tx(&self, id: u32, tags: &Vec<String>, add: bool) -> Result<(), Error> {
+        let (tx, _) = self.get_tx_with_height(false, Some(id), None)?;
+        let mut tx_notes = self.tx_notes()?;
+        if add {
+            tx_notes.add_tags(&tx, tags)
+        } else {
+            tx_notes.remove_tags(&tx, tags)
+        }
+    }
+
+    // Export the transactions of the account that match the filter. Data is taken from the local
+    // tx log, it is not refreshed from the node. Return number of exported transactions.
+    pub fn export_txs(&self, account: Option<String>, filter: &TxFilter, file_name: &str, format: ExportFormat) -> Result<usize, Error> {
+        let wallet_inst = self.get_wallet_instance()?;
+        let data_dir = self.get_data_dir()?;
+        let tx_notes = self.tx_notes()?;
+        let label = match &account {
+            Some(a) => a.clone(),
+            None => self.get_current_account()?.label,
+        };
+
+        let mut txs: Vec<(TxLogEntry, bool)> = api::retrieve_account_txs(wallet_inst, account)?
+            .into_iter()
+            .map(|tx| {
+                let has_proof = tx.tx_slate_id
+                    .map(|uuid| TxProof::has_stored_tx_proof(&data_dir, &uuid.to_string()).unwrap_or(false))
+                    .unwrap_or(false);
+                (tx, has_proof)
+            })
+            .filter(|(tx, has_proof)| filter.matches(tx, *has_proof, &tx_notes))
+            .collect();
+        filter.sort(&mut txs);
+
+        let rows: Vec<TxExportRow> = txs.iter()
+            .map(|(tx, has_proof)| TxExportRow::new(tx, &label, *has_proof, tx_notes.get(tx)))
+            .collect();
+        tx_export::write_rows(file_name, &rows, format)?;
+        Ok(rows.len())
+    }
+
+    // Balance statement of the account for the dates, computed from the local tx log. The balance at
+    // the end of the statement is checked against the outputs when the statement reaches today.
+    pub fn statement(&self, account: Option<String>, from: NaiveDate, to: NaiveDate, interval: Interval) -> Result<Vec<StatementPeriod>, Error> {
+        let wallet_inst = self.get_wallet_instance()?;
+        let txs = api::retrieve_account_txs(wallet_inst.clone(), account.clone())?;
+        let periods = statement::build_statement(&txs, from, to, interval)?;
+
+        if to >= Utc::now().naive_utc().date() {
+            let (tx_balance, _) = statement::balance_at(&txs, Utc::now());
+            let outputs_balance: u64 = api::retrieve_account_outputs(wallet_inst, account, false)?
+                .into_iter()
+                .filter(|o| o.output.status == OutputStatus::Unspent || o.output.status == OutputStatus::Unconfirmed)
+                .map(|o| o.output.value)
+                .sum();
+            if tx_balance != outputs_balance as i64 {
+                println!("WARNING: transaction log balance {} doesn't match the outputs balance {}, the statement might be inaccurate",
+                         statement::signed_amount_to_hr_string(tx_balance),
+                         grin_core::core::amount_to_hr_string(outputs_balance, true));
+            }
+        }
+        Ok(periods)
+    }
+
+    // Print the activity summary of the active account
+    pub fn stats(&self, refresh_from_node: bool) -> Result<(), Error> {
+        let wallet = self.get_wallet_instance()?;
+        let (validated, txs) = api::retrieve_txs_with_proof_flag(wallet.clone(), refresh_from_node, None, None, None, None)?;
+        let (_, outputs) = api::retrieve_outputs(wallet, false, false, None, None, None)?;
+
+        let txs: Vec<TxLogEntry> = txs.into_iter().map(|(tx, _)| tx).collect();
+        let outputs: Vec<OutputData> = outputs.into_iter().map(|o| o.output).collect();
+        WalletStats::new(&txs, &outputs).print();
+
+        if refresh_from_node && !validated {
+            println!("WARNING: wallet was not refreshed from the node, data might be outdated");
+        }
+        Ok(())
+    }
+
+    // Find the transaction by id and/or slate id. Return the transaction and the node height.
+    pub fn get_tx_with_height(&self, refresh_from_node: bool, id: Option<u32>, slate_id: Option<Uuid>) -> Result<(TxLogEntry, u64), Error> {
+        let wallet = self.get_wallet_instance()?;
+        let (_validated, txs) = api::retrieve_txs_with_proof_flag(wallet.clone(),
+                                                                  refresh_from_node,
+                                                                  id,
+                                                                  slate_id,
+                                                                  None,
+                                                                  None)?;
+        let tx = txs.into_iter().next().map(|tpl| tpl.0)
+            .ok_or(ErrorKind::GenericError(format!("could not find transaction {}!",
+                id.map(|id| id.to_string()).or(slate_id.map(|uuid| uuid.to_string())).unwrap_or(String::new()))))?;
+        let (height, _) = api::node_height(wallet)?;
+        Ok((tx, height))
+    }
+
+    // Input commitments of the not confirmed send transaction, from the stored transaction
+    pub fn get_pending_tx_inputs(&self, id: u32) -> Result<Vec<String>, Error> {
+        let (tx, _height) = self.get_tx_with_height(false, Some(id), None)?;
+        if tx.tx_type != TxLogEntryType::TxSent || tx.confirmed {
+            return Err(ErrorKind::GenericError(format!("transaction {} is not a pending send", id)).into());
+        }
+        let slate_id = tx.tx_slate_id
+            .ok_or(ErrorKind::GenericError(format!("transaction {} doesn't have a slate id", id)))?;
+        let stored_tx = api::get_stored_tx(self.get_wallet_instance()?, &slate_id.to_string())
+            .map_err(|_| ErrorKind::GenericError(format!("no transaction data stored for id {}, inputs are unknown", id)))?;
+        Ok(stored_tx.body.inputs.iter().map(|i| grin_util::to_hex(i.commitment().0.to_vec())).collect())
+    }
+
+    // Input of the pending send that safe cancel spends to yourself. It must be known to the wallet,
+    // not frozen and bigger than the fee of the self spend, the node must be online.
+    pub fn safe_cancel_input(&self, id: u32) -> Result<String, Error> {
+        let inputs = self.get_pending_tx_inputs(id)?;
+        let wallet = self.get_wallet_instance()?;
+        let (_height, online) = api::node_height(wallet.clone())?;
+        if !online {
+            return Err(ErrorKind::GenericError(format!("node is offline, transaction {} is not cancelled", id)).into());
+        }
+
+        let coin_control = self.coin_control()?;
+        // The self spend has no change
+        let fee = tx_fee(1, 1, 1, None);
+        let input = api::retrieve_account_outputs(wallet, None, false)?
+            .into_iter()
+            .map(|o| o.output)
+            .filter(|o| o.value > fee)
+            .filter(|o| o.commit.as_ref().map(|c| inputs.contains(c) && !coin_control.is_frozen(c)).unwrap_or(false))
+            .max_by_key(|o| o.value)
+            .and_then(|o| o.commit);
+        Ok(input.ok_or(ErrorKind::GenericError(format!(
+            "none of the inputs of transaction {} can be spent to yourself, it is not cancelled", id)))?)
+    }
+
     pub fn repost(&self, id: u32, fluff: bool) -> Result<(), Error> {
         let wallet = self.get_wallet_instance()?;
         let (_validated, txs) = api::retrieve_txs_with_proof_flag(wallet.clone(),
@@ -491,6 +1154,11 @@ impl Wallet {
         Ok(())
     }
 
+    pub fn cancel_slate(&self, slate_id: Uuid) -> Result<(), Error> {
+        api::cancel_tx(self.get_wallet_instance()?, None, Some(slate_id))?;
+        Ok(())
+    }
+
     pub fn restore_state(&self) -> Result<(), Error> {
         match api::restore(self.get_wallet_instance()?) {
             Ok(_) => return Ok(()),
@@ -529,12 +1197,123 @@ impl Wallet {
         output_amounts: Option<Vec<u64>>,
         dest_acct_name: Option<&str>,
     ) -> Result<(), Error> {
+        // Without dest_acct_name libwallet receives into the `set-recv` account, it can be archived too
+        self.check_receive_account(dest_acct_name)?;
         let s = api::receive_tx(self.get_wallet_instance()?, address, slate,
                                 None, key_id, output_amounts, dest_acct_name).map_err(|e| ErrorKind::GrinWalletReceiveError(format!("{}", e)))?;
         *slate = s;
         Ok(())
     }
 
+    // Pay the invoice: add the inputs and change outputs of the active_account to the invoice slate and lock them.
+    // Frozen outputs are not used.
+    pub fn process_invoice(
+        &self,
+        active_account: Option<String>,
+        address: Option<String>,
+        slate: &Slate,
+        minimum_confirmations: u64,
+        selection_strategy: &str,
+        outputs: Option<Vec<String>>,
+        change_outputs: u32,
+    ) -> Result<Slate, Error> {
+        let (use_all, outputs) = self.strategy_inputs(active_account.clone(), selection_strategy, &outputs, slate.amount,
+                                                      minimum_confirmations, change_outputs, 500, 1)?;
+        let slate = api::process_invoice_tx(
+            self.get_wallet_instance()?,
+            slate,
+            active_account,
+            address.clone(),
+            minimum_confirmations,
+            500,
+            change_outputs,
+            use_all,
+            outputs,
+        )?;
+        // Payer is the second participant of the invoice
+        self.tx_lock_outputs(&slate, address, 1)?;
+        Ok(slate)
+    }
+
+    // Change the queue of the incoming invoices waiting for approval. Expired invoices are discarded first.
+    fn with_invoice_queue<T, F>(&self, expiry_hours: Option<u64>, f: F) -> Result<T, Error>
+        where F: FnOnce(&mut InvoiceQueue) -> Result<T, Error>
+    {
+        invoice_queue::with_queue(&self.get_data_dir()?, |queue| {
+            for invoice in queue.expire(expiry_hours)? {
+                info!("Invoice {} from {} is expired and discarded", invoice.id, invoice.from);
+            }
+            f(queue)
+        })
+    }
+
+    pub fn list_queued_invoices(&self, expiry_hours: Option<u64>) -> Result<(), Error> {
+        let invoices: Vec<QueuedInvoice> = self.with_invoice_queue(expiry_hours, |queue| Ok(queue.invoices.values().cloned().collect()))?;
+        if invoices.is_empty() {
+            println!("No invoices waiting for approval");
+            return Ok(());
+        }
+
+        println!("{:>5}  {:<19}  {:>14}  {:<56}  {}", "Id", "Received", "Amount", "From", "Message");
+        for invoice in &invoices {
+            println!("{:>5}  {:<19}  {:>14}  {:<56}  {}",
+                     invoice.id,
+                     invoice.received.format("%Y-%m-%d %H:%M:%S"),
+                     grin_core::core::amount_to_hr_string(invoice.amount, true),
+                     invoice.from,
+                     invoice.message.clone().unwrap_or(String::new()));
+        }
+        Ok(())
+    }
+
+    pub fn get_queued_invoice(&self, id: u32, expiry_hours: Option<u64>) -> Result<QueuedInvoice, Error> {
+        self.with_invoice_queue(expiry_hours, |queue| Ok(queue.get(id)?.clone()))
+    }
+
+    pub fn remove_queued_invoice(&self, id: u32) -> Result<(), Error> {
+        self.with_invoice_queue(None, |queue| queue.remove(id).map(|_| ()))
+    }
+
+    pub fn create_payment_request(&self, amount: u64, memo: Option<String>, expires: Option<chrono::Duration>) -> Result<PaymentRequest, Error> {
+        PaymentRequests::open(&self.get_data_dir()?)?.create(amount, memo, expires)
+    }
+
+    // Payment requests matched against the receives of all accounts. Receives can go to any account,
+    // depending on the receive account at the time of the payment.
+    fn payment_requests(&self, refresh_from_node: bool) -> Result<PaymentRequests, Error> {
+        let wallet_inst = self.get_wallet_instance()?;
+        let mut requests = PaymentRequests::open(&self.get_data_dir()?)?;
+        if requests.requests.is_empty() {
+            return Ok(requests);
+        }
+
+        if refresh_from_node && !api::sync(wallet_inst.clone(), false)? {
+            println!("WARNING: wallet was not refreshed from the node, data might be outdated");
+        }
+        let mut txs = Vec::new();
+        for acct in api::accounts(wallet_inst.clone())? {
+            for tx in api::retrieve_account_txs(wallet_inst.clone(), Some(acct.label.clone()))? {
+                txs.push((acct.label.clone(), tx));
+            }
+        }
+        requests.update(&txs)?;
+        Ok(requests)
+    }
+
+    pub fn list_payment_requests(&self, refresh_from_node: bool) -> Result<(), Error> {
+        let requests = self.payment_requests(refresh_from_node)?;
+        if requests.requests.is_empty() {
+            println!("No payment requests");
+            return Ok(());
+        }
+        payment_requests::print_table(&requests.requests.values().cloned().collect());
+        Ok(())
+    }
+
+    pub fn get_payment_request(&self, id: u32, refresh_from_node: bool) -> Result<PaymentRequest, Error> {
+        Ok(self.payment_requests(refresh_from_node)?.get(id)?.clone())
+    }
+
     // Lock slate outputs. In other words create output and transaction record at the DB.
     pub fn tx_lock_outputs(&self,
                            slate: &Slate,
@@ -731,7 +1510,13 @@ impl Wallet {
 
         match config.wallet_updater_frequency_sec {
             Some(freq) => {
-                let handler = self.start_updater(None, Duration::from_secs(freq as u64))?;
+                let policy = api::TxPolicy {
+                    repost_blocks: config.auto_repost_blocks,
+                    cancel_expired: config.auto_cancel_expired.unwrap_or(false),
+                    cancel_after_blocks: config.auto_cancel_after_blocks,
+                };
+                let policy = if policy.is_enabled() { Some(policy) } else { None };
+                let handler = self.start_updater(None, Duration::from_secs(freq as u64), policy)?;
                 self.updater_handler = Some(handler);
             },
             _ => (),
@@ -744,9 +1529,13 @@ impl Wallet {
         &self,
         keychain_mask: Option<&SecretKey>,
         frequency: Duration,
+        tx_policy: Option<api::TxPolicy>,
     ) -> Result<JoinHandle<()>, Error> {
 
         self.updater_running.store(true, Ordering::Relaxed);
+        let wallet_inst = self.get_wallet_instance()?;
+        let running = self.updater_running.clone();
+        let reposted_path = Path::new(&self.get_data_dir()?).join(TX_POLICY_REPOSTS_FILE);
 
         let updater = owner_updater::Updater::new(
             self.get_wallet_instance()?,
@@ -772,8 +1561,44 @@ impl Wallet {
         let thread = thread::Builder::new()
             .name("wallet-updater".to_string())
             .spawn(move || {
-                if let Err(e) = updater.run(frequency, keychain_mask, &tx_inner ) {
-                    error!("Wallet state updater failed with error: {:?}", e);
+                let policy = match tx_policy {
+                    Some(policy) => policy,
+                    None => {
+                        if let Err(e) = updater.run(frequency, keychain_mask, &tx_inner ) {
+                            error!("Wallet state updater failed with error: {:?}", e);
+                        }
+                        return;
+                    }
+                };
+
+                // The same loop as the updater runs, the policy is applied after every update.
+                // Reposts are stored, so a restart doesn't repost every stuck transaction at once.
+                let mut reposted: HashMap<String, u64> = json_store::load(&reposted_path, "transaction reposts")
+                    .unwrap_or_else(|e| {
+                        warn!("{}", e);
+                        None
+                    })
+                    .unwrap_or_default();
+                while running.load(Ordering::Relaxed) {
+                    match grin_wallet_libwallet::owner::update_wallet_state(wallet_inst.clone(), keychain_mask.as_ref(), &tx_inner) {
+                        Ok(_) => {
+                            let before = reposted.clone();
+                            if let Err(e) = api::apply_tx_policy(wallet_inst.clone(), &policy, &mut reposted) {
+                                warn!("Unable to apply transaction policies, {}", e);
+                            }
+                            if reposted != before {
+                                if let Err(e) = json_store::save(&reposted_path, &reposted) {
+                                    warn!("Unable to store transaction reposts, {}", e);
+                                }
+                            }
+                        },
+                        Err(e) => error!("Wallet state update failed with error: {:?}", e),
+                    }
+
+                    let next_run = Instant::now() + frequency;
+                    while running.load(Ordering::Relaxed) && Instant::now() < next_run {
+                        thread::sleep(Duration::from_secs(1));
+                    }
                 }
             })?;
         Ok(thread)